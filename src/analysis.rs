@@ -0,0 +1,329 @@
+// ============================================================================
+// src/analysis.rs - Análise da série temporal de amostras da atividade
+// ============================================================================
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use crate::parsers::{ActivityData, Sample, TimerEvent};
+
+/// Raio médio da Terra em metros (usado no cálculo de distância por GPS)
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+/// Tolerância (segundos) para associar um instante fora da série de amostras
+const POINT_MATCH_TOLERANCE_SECONDS: i64 = 120;
+
+/// Resultado do cálculo de tempo em movimento
+#[derive(Debug, Clone, Copy)]
+pub struct MovingStats {
+    pub moving_time_seconds: f64,
+    pub moving_distance_meters: f64,
+}
+
+/// Calcula o tempo e a distância em movimento a partir das amostras.
+///
+/// Um intervalo entre duas amostras consecutivas conta como "em movimento" quando
+/// o cronômetro estava ativo no início do intervalo (eventos start/stop do FIT) e
+/// a velocidade no intervalo é maior ou igual a `speed_threshold_mps`.
+/// Retorna `None` quando as amostras não têm distância, velocidade nem posição.
+pub fn compute_moving_stats(
+    samples: &[Sample],
+    timer_events: &[TimerEvent],
+    speed_threshold_mps: f64,
+) -> Option<MovingStats> {
+    let mut moving_time_seconds = 0.0;
+    let mut moving_distance_meters = 0.0;
+    let mut has_distance_data = false;
+
+    for pair in samples.windows(2) {
+        let (prev, curr) = (&pair[0], &pair[1]);
+        let dt = (curr.timestamp - prev.timestamp).num_milliseconds() as f64 / 1000.0;
+        if dt <= 0.0 {
+            continue;
+        }
+
+        let Some(segment_distance) = segment_distance(prev, curr, dt) else {
+            continue;
+        };
+        has_distance_data = true;
+
+        if !is_timer_running(timer_events, prev.timestamp) {
+            continue;
+        }
+
+        if segment_distance / dt >= speed_threshold_mps {
+            moving_time_seconds += dt;
+            moving_distance_meters += segment_distance;
+        }
+    }
+
+    if !has_distance_data {
+        return None;
+    }
+
+    Some(MovingStats {
+        moving_time_seconds,
+        moving_distance_meters,
+    })
+}
+
+/// Estatísticas da atividade em um instante específico (ex: momento da foto)
+#[derive(Debug, Clone, Copy)]
+pub struct PointStats {
+    pub elapsed_seconds: f64,
+    pub distance_meters: Option<f64>,
+    pub heart_rate: Option<u8>,
+    pub altitude_meters: Option<f64>,
+}
+
+/// Localiza o instante `at` na série de amostras e retorna as estatísticas
+/// acumuladas até ali. Retorna `None` se o instante estiver fora da atividade.
+pub fn stats_at(activity_data: &ActivityData, at: DateTime<Utc>) -> Option<PointStats> {
+    let samples = &activity_data.samples;
+    let first = samples.first()?;
+    let last = samples.last()?;
+    let tolerance = chrono::Duration::seconds(POINT_MATCH_TOLERANCE_SECONDS);
+    if at < first.timestamp - tolerance || at > last.timestamp + tolerance {
+        return None;
+    }
+
+    // Última amostra registrada até o instante (ou a primeira, se antes do início)
+    let index = samples.partition_point(|sample| sample.timestamp <= at).saturating_sub(1);
+    let sample = &samples[index];
+
+    let distance_meters = sample.distance_meters.or_else(|| {
+        let mut total = 0.0;
+        let mut has_position = false;
+        for pair in samples[..=index].windows(2) {
+            if let (Some(lat1), Some(lon1), Some(lat2), Some(lon2)) =
+                (pair[0].latitude, pair[0].longitude, pair[1].latitude, pair[1].longitude)
+            {
+                total += haversine_meters(lat1, lon1, lat2, lon2);
+                has_position = true;
+            }
+        }
+        has_position.then_some(total)
+    });
+
+    Some(PointStats {
+        elapsed_seconds: ((at - activity_data.start_time).num_seconds().max(0)) as f64,
+        distance_meters,
+        heart_rate: sample.heart_rate,
+        altitude_meters: sample.altitude_meters,
+    })
+}
+
+/// Tempo passado em uma zona de frequência cardíaca
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct HeartRateZone {
+    pub zone: u8,
+    pub min_bpm: u8,
+    /// Limite superior (exclusivo); `None` na última zona
+    pub max_bpm: Option<u8>,
+    pub seconds: f64,
+}
+
+/// Tempo em cada zona de FC, somando os intervalos entre amostras com o
+/// cronômetro ativo pela FC do início do intervalo. `lower_percents` são os
+/// limites inferiores (crescentes) das zonas em % de `max_hr`, como em
+/// [`crate::constants::HR_ZONE_LOWER_PERCENTS`]; o tempo abaixo da primeira
+/// zona não é contado.
+pub fn heart_rate_zones(
+    samples: &[Sample],
+    timer_events: &[TimerEvent],
+    max_hr: u8,
+    lower_percents: &[u8],
+) -> Vec<HeartRateZone> {
+    let bounds: Vec<u8> = lower_percents
+        .iter()
+        .map(|percent| (max_hr as u32 * *percent as u32 / 100) as u8)
+        .collect();
+    let seconds = if max_hr == 0 {
+        vec![0.0; bounds.len()]
+    } else {
+        let lower: Vec<u32> = bounds.iter().map(|bpm| *bpm as u32).collect();
+        time_in_zones(samples, timer_events, &lower, |sample| sample.heart_rate.map(u32::from))
+    };
+    bounds
+        .iter()
+        .zip(seconds)
+        .enumerate()
+        .map(|(index, (min_bpm, seconds))| HeartRateZone {
+            zone: index as u8 + 1,
+            min_bpm: *min_bpm,
+            max_bpm: bounds.get(index + 1).copied(),
+            seconds,
+        })
+        .collect()
+}
+
+/// Tempo passado em uma zona de potência
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PowerZone {
+    pub zone: u8,
+    pub min_watts: u16,
+    /// Limite superior (exclusivo); `None` na última zona
+    pub max_watts: Option<u16>,
+    pub seconds: f64,
+}
+
+/// Tempo em cada zona de potência, como em [`heart_rate_zones`], com os
+/// limites em % do FTP (ver [`crate::constants::POWER_ZONE_LOWER_PERCENTS`])
+pub fn power_zones(
+    samples: &[Sample],
+    timer_events: &[TimerEvent],
+    ftp: u16,
+    lower_percents: &[u8],
+) -> Vec<PowerZone> {
+    let bounds: Vec<u16> = lower_percents
+        .iter()
+        .map(|percent| (ftp as u32 * *percent as u32 / 100) as u16)
+        .collect();
+    let seconds = if ftp == 0 {
+        vec![0.0; bounds.len()]
+    } else {
+        let lower: Vec<u32> = bounds.iter().map(|watts| *watts as u32).collect();
+        time_in_zones(samples, timer_events, &lower, |sample| sample.power_watts.map(u32::from))
+    };
+    bounds
+        .iter()
+        .zip(seconds)
+        .enumerate()
+        .map(|(index, (min_watts, seconds))| PowerZone {
+            zone: index as u8 + 1,
+            min_watts: *min_watts,
+            max_watts: bounds.get(index + 1).copied(),
+            seconds,
+        })
+        .collect()
+}
+
+/// Segundos em cada zona (limites inferiores crescentes em `lower`), somando
+/// os intervalos com o cronômetro ativo pelo valor do início do intervalo
+fn time_in_zones(
+    samples: &[Sample],
+    timer_events: &[TimerEvent],
+    lower: &[u32],
+    value: impl Fn(&Sample) -> Option<u32>,
+) -> Vec<f64> {
+    let mut seconds = vec![0.0; lower.len()];
+    for pair in samples.windows(2) {
+        let (prev, curr) = (&pair[0], &pair[1]);
+        let dt = (curr.timestamp - prev.timestamp).num_milliseconds() as f64 / 1000.0;
+        let Some(current) = value(prev) else {
+            continue;
+        };
+        if dt <= 0.0 || !is_timer_running(timer_events, prev.timestamp) {
+            continue;
+        }
+        if let Some(zone) = lower.iter().rposition(|min| current >= *min) {
+            seconds[zone] += dt;
+        }
+    }
+    seconds
+}
+
+/// Ganho de elevação: soma das subidas de pelo menos `threshold` metros acima
+/// do último ponto baixo, ignorando oscilações menores do altímetro. `None`
+/// quando as amostras não têm altitude.
+pub fn elevation_gain(samples: &[Sample], threshold: f64) -> Option<f64> {
+    let mut altitudes = samples.iter().filter_map(|sample| sample.altitude_meters);
+    let mut reference = altitudes.next()?;
+    let mut gain = 0.0;
+    for altitude in altitudes {
+        if altitude < reference {
+            reference = altitude;
+        } else if altitude - reference >= threshold {
+            gain += altitude - reference;
+            reference = altitude;
+        }
+    }
+    Some(gain)
+}
+
+/// Distância percorrida entre duas amostras, usando (em ordem de preferência)
+/// a distância acumulada, a velocidade instantânea ou as coordenadas GPS
+fn segment_distance(prev: &Sample, curr: &Sample, dt: f64) -> Option<f64> {
+    if let (Some(a), Some(b)) = (prev.distance_meters, curr.distance_meters) {
+        if b >= a {
+            return Some(b - a);
+        }
+    }
+
+    if let Some(speed) = curr.speed_mps {
+        return Some(speed * dt);
+    }
+
+    match (prev.latitude, prev.longitude, curr.latitude, curr.longitude) {
+        (Some(lat1), Some(lon1), Some(lat2), Some(lon2)) => Some(haversine_meters(lat1, lon1, lat2, lon2)),
+        _ => None,
+    }
+}
+
+/// Verifica se o cronômetro estava ativo no instante informado.
+/// Sem eventos registrados, considera o cronômetro sempre ativo.
+fn is_timer_running(timer_events: &[TimerEvent], at: DateTime<Utc>) -> bool {
+    let mut running = true;
+    for event in timer_events {
+        match *event {
+            TimerEvent::Start(time) if time <= at => running = true,
+            TimerEvent::Stop(time) if time <= at => running = false,
+            _ => {}
+        }
+    }
+    running
+}
+
+/// Distância entre duas coordenadas (graus) pela fórmula de haversine
+pub fn haversine_meters(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 8, 20, 9, 0, 0).unwrap() + chrono::Duration::seconds(seconds)
+    }
+
+    /// Amostras a cada 10 s com as distâncias acumuladas informadas
+    fn samples(distances: &[f64]) -> Vec<Sample> {
+        distances
+            .iter()
+            .enumerate()
+            .map(|(i, &distance)| Sample { timestamp: at(i as i64 * 10), distance_meters: Some(distance), ..Sample::default() })
+            .collect()
+    }
+
+    #[test]
+    fn timer_stop_excludes_the_paused_interval() {
+        // 3 m/s o tempo todo, mas o cronômetro fica parado entre 10 s e 20 s
+        let samples = samples(&[0.0, 30.0, 60.0, 90.0]);
+        let events = [TimerEvent::Start(at(0)), TimerEvent::Stop(at(10)), TimerEvent::Start(at(20))];
+
+        let stats = compute_moving_stats(&samples, &events, 0.5).unwrap();
+        assert_eq!(stats.moving_time_seconds, 20.0);
+        assert_eq!(stats.moving_distance_meters, 60.0);
+    }
+
+    #[test]
+    fn slow_interval_is_not_counted_as_moving() {
+        // Parado no semáforo entre 10 s e 20 s (0,2 m/s, abaixo do limite)
+        let samples = samples(&[0.0, 30.0, 32.0, 62.0]);
+
+        let stats = compute_moving_stats(&samples, &[], 0.5).unwrap();
+        assert_eq!(stats.moving_time_seconds, 20.0);
+        assert_eq!(stats.moving_distance_meters, 60.0);
+    }
+
+    #[test]
+    fn samples_without_distance_speed_or_position_give_none() {
+        let samples: Vec<Sample> = (0..3).map(|i| Sample { timestamp: at(i * 10), ..Sample::default() }).collect();
+        assert!(compute_moving_stats(&samples, &[], 0.5).is_none());
+    }
+}
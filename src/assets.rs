@@ -0,0 +1,194 @@
+// ============================================================================
+// src/assets.rs - Fontes e marcas d'água: embutidas no binário, com
+// substituição por diretórios do usuário
+// ============================================================================
+
+use rusttype::Font;
+use std::borrow::Cow;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use crate::constants::CONFIG_DIR_NAME;
+use crate::error::{AppError, AppResult};
+use crate::fonts;
+
+/// Recursos padrão do overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Asset {
+    TextFont,
+    /// Variante negrito da fonte de texto (`--font-weight`/`--number-weight bold`)
+    BoldFont,
+    IconFont,
+    WatermarkWhite,
+    WatermarkBlack,
+}
+
+/// De onde um recurso foi carregado
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetSource {
+    File(PathBuf),
+    /// Cópia embutida no binário (feature `embedded-assets`)
+    Embedded,
+}
+
+/// Conteúdo de um recurso e sua origem
+#[derive(Debug, Clone)]
+pub struct AssetData {
+    pub data: Cow<'static, [u8]>,
+    pub source: AssetSource,
+}
+
+/// Fonte carregada; mantém os bytes originais para embutir no SVG
+#[derive(Clone)]
+pub struct FontAsset {
+    font: Font<'static>,
+    /// Bytes das fontes embutidas; os de arquivos ficam só dentro do `font`
+    embedded: Option<&'static [u8]>,
+    pub source: AssetSource,
+    /// Índice da fonte em coleções (.ttc); 0 em arquivos simples
+    pub index: u32,
+    /// Nome da família (tabela `name` da fonte), usado no CSS do SVG
+    pub family: String,
+    /// Peso CSS da fonte (400 normal, 700 negrito)
+    pub weight: u16,
+}
+
+/// Diretórios adicionados em tempo de execução (ex: `assets-dir` da configuração)
+static SEARCH_DIRS: RwLock<Vec<PathBuf>> = RwLock::new(Vec::new());
+
+impl Asset {
+    /// Caminho dentro de um diretório de recursos (mesma estrutura do repositório)
+    pub fn relative_path(self) -> &'static str {
+        match self {
+            Asset::TextFont => "fonts/DejaVuSans.ttf",
+            Asset::BoldFont => "fonts/DejaVuSans-Bold.ttf",
+            Asset::IconFont => "fonts/FontAwesome.ttf",
+            Asset::WatermarkWhite => "img/garmin_white.png",
+            Asset::WatermarkBlack => "img/garmin_black.png",
+        }
+    }
+
+    #[cfg(feature = "embedded-assets")]
+    fn embedded(self) -> Option<&'static [u8]> {
+        Some(match self {
+            Asset::TextFont => include_bytes!("../fonts/DejaVuSans.ttf"),
+            Asset::BoldFont => include_bytes!("../fonts/DejaVuSans-Bold.ttf"),
+            Asset::IconFont => include_bytes!("../fonts/FontAwesome.ttf"),
+            Asset::WatermarkWhite => include_bytes!("../img/garmin_white.png"),
+            Asset::WatermarkBlack => include_bytes!("../img/garmin_black.png"),
+        })
+    }
+
+    #[cfg(not(feature = "embedded-assets"))]
+    fn embedded(self) -> Option<&'static [u8]> {
+        None
+    }
+}
+
+impl fmt::Display for AssetSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetSource::File(path) => write!(f, "{}", path.display()),
+            AssetSource::Embedded => f.write_str("embutido no binário"),
+        }
+    }
+}
+
+impl FontAsset {
+    /// Carrega uma fonte de um arquivo escolhido pelo usuário; `index`
+    /// seleciona a fonte dentro de coleções (.ttc)
+    pub fn from_file(path: &Path, index: u32) -> AppResult<FontAsset> {
+        let data = fs::read(path).map_err(|source| match source.kind() {
+            io::ErrorKind::NotFound => AppError::MissingAsset { kind: "Fonte", path: path.to_path_buf() },
+            _ => AppError::FileError { path: path.to_path_buf(), source },
+        })?;
+        Self::from_data(Cow::Owned(data), index, AssetSource::File(path.to_path_buf()))
+    }
+
+    fn from_data(data: Cow<'static, [u8]>, index: u32, source: AssetSource) -> AppResult<FontAsset> {
+        let invalid = || AppError::FontError(format!("Fonte inválida: {}", source));
+        let face = ttf_parser::Face::from_slice(&data, index).map_err(|_| invalid())?;
+        let family = fonts::face_families(face.names()).into_iter().next().unwrap_or_else(|| "sans-serif".to_string());
+        let weight = face.weight().to_number();
+        // O rusttype fica com o único buffer da fonte; `data()` o lê de volta
+        let (font, embedded) = match data {
+            Cow::Borrowed(bytes) => (Font::try_from_bytes_and_index(bytes, index), Some(bytes)),
+            Cow::Owned(bytes) => (Font::try_from_vec_and_index(bytes, index), None),
+        };
+        let font = font.ok_or_else(invalid)?;
+        Ok(FontAsset { font, embedded, source, index, family, weight })
+    }
+
+    /// Bytes do arquivo da fonte (a coleção inteira em .ttc)
+    pub fn data(&self) -> &[u8] {
+        match &self.font {
+            Font::Owned(face) => face.as_slice(),
+            Font::Ref(_) => self.embedded.unwrap_or_default(),
+        }
+    }
+
+    /// Indica se a fonte tem um glifo para o caractere (espaços sempre contam)
+    pub fn has_glyph(&self, c: char) -> bool {
+        c.is_whitespace() || c.is_control() || self.font.glyph(c).id().0 != 0
+    }
+}
+
+impl Deref for FontAsset {
+    type Target = Font<'static>;
+
+    fn deref(&self) -> &Font<'static> {
+        &self.font
+    }
+}
+
+/// Adiciona um diretório de recursos com prioridade sobre os anteriores.
+/// Os arquivos seguem a estrutura `fonts/...` e `img/...`.
+pub fn add_search_dir(dir: PathBuf) {
+    if let Ok(mut dirs) = SEARCH_DIRS.write() {
+        dirs.insert(0, dir);
+    }
+}
+
+/// Diretórios pesquisados, em ordem: os adicionados e o diretório de dados
+/// do usuário (~/.local/share/tcx_image_overlay)
+pub fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs = SEARCH_DIRS.read().map(|dirs| dirs.clone()).unwrap_or_default();
+    dirs.extend(dirs::data_dir().map(|dir| dir.join(CONFIG_DIR_NAME)));
+    dirs
+}
+
+/// Localiza o recurso nos diretórios e, sem substituto, usa a cópia
+/// embutida. O erro lista os locais pesquisados.
+pub fn load(asset: Asset) -> Result<AssetData, Vec<String>> {
+    let mut searched = Vec::new();
+    for dir in search_dirs() {
+        let path = dir.join(asset.relative_path());
+        if let Ok(data) = fs::read(&path) {
+            return Ok(AssetData { data: Cow::Owned(data), source: AssetSource::File(path) });
+        }
+        searched.push(path.display().to_string());
+    }
+    match asset.embedded() {
+        Some(data) => Ok(AssetData { data: Cow::Borrowed(data), source: AssetSource::Embedded }),
+        None => {
+            searched.push("binário compilado sem a feature embedded-assets".to_string());
+            Err(searched)
+        }
+    }
+}
+
+/// Carrega uma das fontes padrão
+pub fn load_font(asset: Asset) -> AppResult<FontAsset> {
+    let AssetData { data, source } = load(asset).map_err(|searched| {
+        AppError::FontError(format!(
+            "Fonte '{}' não encontrada. Locais pesquisados: {}",
+            asset.relative_path(),
+            searched.join(", ")
+        ))
+    })?;
+    FontAsset::from_data(data, 0, source)
+}
@@ -0,0 +1,389 @@
+// ============================================================================
+// src/batch.rs - Modo batch: associa várias fotos às atividades e processa em paralelo
+// ============================================================================
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use rayon::prelude::*;
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::{BatchArgs, OverlayArgs};
+use crate::logging;
+use tcx_image_overlay::{
+    parse_activity_file, photo_capture_time, resolve_offset, resolve_start_time, ActivityData, AppError, AppResult,
+    ImageProcessor, SaveOutcome, TemplateContext, TimezoneOverride,
+};
+
+/// Tolerância (segundos) antes do início/depois do fim da atividade para
+/// associar uma foto a ela (ex: foto logo após terminar o treino)
+const BATCH_MATCH_TOLERANCE_SECONDS: i64 = 15 * 60;
+
+/// Extensões de foto aceitas ao varrer diretórios
+pub const PHOTO_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "tif", "tiff", "bmp"];
+/// Extensões de atividade aceitas ao varrer diretórios (inclui exportações compactadas)
+pub const ACTIVITY_EXTENSIONS: &[&str] = &["tcx", "fit", "gpx", "gz", "zip"];
+
+/// Critério de associação entre foto e atividade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum MatchMode {
+    /// Horário EXIF e, se não houver, nome do arquivo
+    #[default]
+    Auto,
+    /// Apenas o horário EXIF dentro da janela da atividade
+    Exif,
+    /// Apenas o nome: a foto contém o nome do arquivo da atividade
+    Filename,
+}
+
+/// Atividade carregada com o caminho de origem
+pub struct LoadedActivity {
+    pub path: PathBuf,
+    pub data: ActivityData,
+}
+
+/// Resultado do processamento de uma foto
+enum BatchStatus {
+    Processed(PathBuf),
+    Skipped(String),
+    Failed(String),
+}
+
+/// Linha da tabela de resumo
+struct BatchOutcome {
+    photo: PathBuf,
+    activity: Option<PathBuf>,
+    status: BatchStatus,
+}
+
+/// Executa o subcomando `batch`
+pub fn run(args: &BatchArgs) -> AppResult<()> {
+    let photo_paths = collect_files(&args.photos, PHOTO_EXTENSIONS)?;
+    let activity_paths = collect_files(&args.activities, ACTIVITY_EXTENSIONS)?;
+    info!("📦 Batch: {} foto(s), {} atividade(s)", photo_paths.len(), activity_paths.len());
+
+    if let Some(jobs) = args.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs.max(1))
+            .build_global()
+            .map_err(|e| AppError::InvalidFormat(format!("Falha ao configurar paralelismo: {}", e)))?;
+    }
+
+    // Atividades com erro de leitura não impedem as demais
+    let activities: Vec<LoadedActivity> = activity_paths
+        .par_iter()
+        .filter_map(|path| match parse_activity_file(path) {
+            Ok(data) => Some(LoadedActivity { path: path.clone(), data }),
+            Err(e) => {
+                warn!("⚠️  Ignorando atividade {:?}: {}", path, e);
+                None
+            }
+        })
+        .collect();
+
+    let matches: Vec<Option<&LoadedActivity>> = photo_paths
+        .par_iter()
+        .map(|photo| match_activity(photo, &activities, args.match_mode, args.overlay.timezone.as_ref()))
+        .collect();
+    let plans = plan_outputs(&photo_paths, &matches, args);
+
+    let outcomes: Vec<BatchOutcome> = photo_paths
+        .par_iter()
+        .zip(matches.par_iter().zip(plans.par_iter()))
+        .map(|(photo, (activity, plan))| process_photo(photo, *activity, plan, args))
+        .collect();
+
+    print_summary(&outcomes);
+    Ok(())
+}
+
+/// Nome de saída de cada foto, decidido antes do processamento paralelo
+enum OutputPlan {
+    /// Nome automático da própria foto
+    Default,
+    /// Outro `{photo_stem}`, porque o nome da foto gera a mesma saída de outra
+    Renamed(String),
+    /// Mesma saída de outra foto, e o modelo não usa `{photo_stem}`
+    Duplicate(PathBuf),
+}
+
+/// Detecta fotos que gravariam no mesmo arquivo (ex: `a/IMG_0001.jpg` e
+/// `b/IMG_0001.jpg`) e acrescenta -2, -3... ao nome das repetidas
+fn plan_outputs(photos: &[PathBuf], matches: &[Option<&LoadedActivity>], args: &BatchArgs) -> Vec<OutputPlan> {
+    let mut claimed: HashMap<PathBuf, &Path> = HashMap::new();
+    let mut plans = Vec::with_capacity(photos.len());
+
+    for (photo, activity) in photos.iter().zip(matches) {
+        let (Some(activity), Some(stem)) = (activity, photo.file_stem().and_then(|s| s.to_str())) else {
+            plans.push(OutputPlan::Default);
+            continue;
+        };
+        // Erros no modelo aparecem no processamento da própria foto
+        let planned = |stem: &str| planned_output_path(photo, stem, activity, &args.overlay, args.output_dir.as_deref()).ok();
+        let Some(output) = planned(stem) else {
+            plans.push(OutputPlan::Default);
+            continue;
+        };
+
+        let Some(first) = claimed.get(&output).copied() else {
+            claimed.insert(output, photo);
+            plans.push(OutputPlan::Default);
+            continue;
+        };
+        let renamed = (2..).map(|index| format!("{}-{}", stem, index)).find_map(|renamed| match planned(&renamed) {
+            Some(candidate) if candidate == output => Some(None),
+            Some(candidate) if !claimed.contains_key(&candidate) => Some(Some((renamed, candidate))),
+            Some(_) => None,
+            None => Some(None),
+        });
+        match renamed.flatten() {
+            Some((renamed, candidate)) => {
+                info!("🔀 {:?} tem o mesmo nome de saída que {:?}; usando '{}'", photo, first, renamed);
+                claimed.insert(candidate, photo);
+                plans.push(OutputPlan::Renamed(renamed));
+            }
+            None => plans.push(OutputPlan::Duplicate(first.to_path_buf())),
+        }
+    }
+    plans
+}
+
+/// Caminho da saída automática da foto, calculado sem decodificar a imagem
+/// (mesmas regras de `ImageProcessor::new` e do modelo `--output-template`)
+fn planned_output_path(
+    photo: &Path,
+    photo_stem: &str,
+    activity: &LoadedActivity,
+    overlay: &OverlayArgs,
+    output_dir: Option<&Path>,
+) -> AppResult<PathBuf> {
+    let extension = match overlay.format {
+        Some(format) => format.extension(),
+        None => photo.extension().and_then(|e| e.to_str()).unwrap_or("jpg"),
+    };
+    let (start_time_local, _) = resolve_start_time(&activity.data, overlay.timezone.as_ref());
+    let context = TemplateContext {
+        photo_stem,
+        kind: "stats-overlay",
+        extension,
+        activity_date: start_time_local,
+        sport: activity.data.sport.as_deref(),
+        device: &activity.data.device_name,
+    };
+    overlay.output_template.clone().unwrap_or_default().render_in(&context, output_dir)
+}
+
+/// Gera a imagem final da foto com a atividade associada
+fn process_photo(photo: &Path, activity: Option<&LoadedActivity>, plan: &OutputPlan, args: &BatchArgs) -> BatchOutcome {
+    let Some(activity) = activity else {
+        return BatchOutcome {
+            photo: photo.to_path_buf(),
+            activity: None,
+            status: BatchStatus::Skipped("nenhuma atividade correspondente".to_string()),
+        };
+    };
+
+    let photo_stem = match plan {
+        OutputPlan::Default => None,
+        OutputPlan::Renamed(stem) => Some(stem.as_str()),
+        OutputPlan::Duplicate(first) => {
+            return BatchOutcome {
+                photo: photo.to_path_buf(),
+                activity: Some(activity.path.clone()),
+                status: BatchStatus::Skipped(format!("mesma saída que {}", first.display())),
+            };
+        }
+    };
+
+    let status = match render_photo(photo, photo_stem, activity, &args.overlay, args.output_dir.as_deref()) {
+        Ok(SaveOutcome::Written(output)) => BatchStatus::Processed(output),
+        Ok(SaveOutcome::Skipped(output)) => BatchStatus::Skipped(format!("{} já existe", output.display())),
+        Err(e) => BatchStatus::Failed(e.to_string()),
+    };
+    BatchOutcome {
+        photo: photo.to_path_buf(),
+        activity: Some(activity.path.clone()),
+        status,
+    }
+}
+
+/// Executa o mesmo pipeline do modo simples para uma foto; sem `output_dir`,
+/// usa a saída automática (modelo `--output-template`). `photo_stem`
+/// substitui o nome da foto no caminho gerado.
+pub fn render_photo(
+    photo: &Path,
+    photo_stem: Option<&str>,
+    activity: &LoadedActivity,
+    overlay: &OverlayArgs,
+    output_dir: Option<&Path>,
+) -> AppResult<SaveOutcome> {
+    let mut processor = ImageProcessor::new(&photo.to_path_buf())?;
+    overlay.configure(&mut processor)?;
+    if let Some(photo_stem) = photo_stem {
+        processor.set_photo_stem(photo_stem);
+    }
+
+    // Mantém o nome gerado automaticamente, trocando apenas o diretório
+    if let Some(output_dir) = output_dir {
+        processor.set_output_dir(output_dir.to_path_buf());
+    }
+
+    processor.process_activity(&activity.data)?;
+    if let (true, Some(explanation)) = (overlay.explain, processor.layout_explanation()) {
+        logging::explain(processor.output_path(), explanation);
+    }
+    processor.save_result()
+}
+
+/// Escolhe a atividade da foto de acordo com o critério configurado
+pub fn match_activity<'a>(
+    photo: &Path,
+    activities: &'a [LoadedActivity],
+    mode: MatchMode,
+    timezone_override: Option<&TimezoneOverride>,
+) -> Option<&'a LoadedActivity> {
+    let by_exif = || match_by_capture_time(photo, activities, timezone_override);
+    let by_name = || match_by_filename(photo, activities);
+    match mode {
+        MatchMode::Auto => by_exif().or_else(by_name),
+        MatchMode::Exif => by_exif(),
+        MatchMode::Filename => by_name(),
+    }
+}
+
+/// Atividade cuja janela (início ao fim, com tolerância) contém o horário EXIF.
+/// O horário da foto sem deslocamento é interpretado no fuso de cada atividade.
+fn match_by_capture_time<'a>(
+    photo: &Path,
+    activities: &'a [LoadedActivity],
+    timezone_override: Option<&TimezoneOverride>,
+) -> Option<&'a LoadedActivity> {
+    let bytes = fs::read(photo).ok()?;
+    let capture_time = photo_capture_time(&bytes)?;
+    let tolerance = chrono::Duration::seconds(BATCH_MATCH_TOLERANCE_SECONDS);
+
+    activities
+        .iter()
+        .filter_map(|activity| {
+            let (offset, _) = resolve_offset(
+                &activity.data,
+                activity.data.start_time,
+                timezone_override,
+            );
+            let taken_at = capture_time.to_utc(offset)?;
+            let distance = distance_to_window(taken_at, activity.data.start_time, activity.data.end_time());
+            (distance <= tolerance).then_some((distance, activity))
+        })
+        .min_by_key(|(distance, activity)| (*distance, activity.data.start_time))
+        .map(|(_, activity)| activity)
+}
+
+/// Distância de um instante até a janela [início, fim]; zero quando está dentro
+fn distance_to_window(at: DateTime<Utc>, start: DateTime<Utc>, end: DateTime<Utc>) -> chrono::Duration {
+    if at < start {
+        start - at
+    } else if at > end {
+        at - end
+    } else {
+        chrono::Duration::zero()
+    }
+}
+
+/// Atividade cujo nome de arquivo está contido no nome da foto
+/// (ex: "corrida-matinal.fit" e "corrida-matinal-2.jpg"); vence o nome mais longo
+fn match_by_filename<'a>(photo: &Path, activities: &'a [LoadedActivity]) -> Option<&'a LoadedActivity> {
+    let photo_stem = file_stem_lowercase(photo)?;
+    activities
+        .iter()
+        .filter_map(|activity| Some((file_stem_lowercase(&activity.path)?, activity)))
+        .filter(|(stem, _)| !stem.is_empty() && photo_stem.contains(stem.as_str()))
+        .max_by_key(|(stem, _)| stem.len())
+        .map(|(_, activity)| activity)
+}
+
+/// Nome sem extensão, em minúsculas; exportações compactadas perdem as duas
+/// extensões ("corrida.fit.gz" → "corrida")
+fn file_stem_lowercase(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?.to_lowercase();
+    let is_compressed = has_extension(path, &["gz", "zip"]);
+    match Path::new(&stem).file_stem().and_then(|s| s.to_str()) {
+        Some(inner) if is_compressed && has_extension(Path::new(&stem), &["tcx", "fit"]) => Some(inner.to_string()),
+        _ => Some(stem),
+    }
+}
+
+/// Expande diretórios, padrões glob e arquivos em uma lista ordenada e sem duplicatas
+pub fn collect_files(inputs: &[String], extensions: &[&str]) -> AppResult<Vec<PathBuf>> {
+    let has_extension = |path: &Path| has_extension(path, extensions);
+
+    let mut files = Vec::new();
+    for input in inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            for entry in fs::read_dir(path)? {
+                let entry_path = entry?.path();
+                if entry_path.is_file() && has_extension(&entry_path) {
+                    files.push(entry_path);
+                }
+            }
+        } else if path.is_file() {
+            files.push(path.to_path_buf());
+        } else {
+            let matches = glob::glob(input)
+                .map_err(|e| AppError::InvalidFormat(format!("Padrão inválido '{}': {}", input, e)))?;
+            let before = files.len();
+            files.extend(matches.filter_map(Result::ok).filter(|p| p.is_file() && has_extension(p)));
+            if files.len() == before {
+                warn!("⚠️  Nenhum arquivo encontrado em '{}'", input);
+            }
+        }
+    }
+
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Indica se a extensão do arquivo (sem diferenciar maiúsculas) está na lista
+pub fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.contains(&e.to_lowercase().as_str()))
+}
+
+/// Imprime a tabela de resumo com sucessos, ignorados e falhas
+fn print_summary(outcomes: &[BatchOutcome]) {
+    let name = |path: &Path| path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+    let rows: Vec<(String, String, &str, String)> = outcomes
+        .iter()
+        .map(|outcome| {
+            let activity = outcome.activity.as_deref().map_or_else(|| "-".to_string(), name);
+            let (status, detail) = match &outcome.status {
+                BatchStatus::Processed(output) => ("✅ ok", output.display().to_string()),
+                BatchStatus::Skipped(reason) => ("⏭️  ignorada", reason.clone()),
+                BatchStatus::Failed(error) => ("❌ falha", error.clone()),
+            };
+            (name(&outcome.photo), activity, status, detail)
+        })
+        .collect();
+
+    let photo_width = rows.iter().map(|r| r.0.chars().count()).max().unwrap_or(0).max("Foto".len());
+    let activity_width = rows.iter().map(|r| r.1.chars().count()).max().unwrap_or(0).max("Atividade".len());
+
+    println!();
+    println!("=== Resumo do batch ===");
+    println!("{:<pw$}  {:<aw$}  {:<12}  Detalhe", "Foto", "Atividade", "Status", pw = photo_width, aw = activity_width);
+    for (photo, activity, status, detail) in &rows {
+        println!("{:<pw$}  {:<aw$}  {:<12}  {}", photo, activity, status, detail, pw = photo_width, aw = activity_width);
+    }
+
+    let count = |f: fn(&BatchStatus) -> bool| outcomes.iter().filter(|o| f(&o.status)).count();
+    println!(
+        "\n✅ {} processada(s) | ⏭️  {} ignorada(s) | ❌ {} falha(s)",
+        count(|s| matches!(s, BatchStatus::Processed(_))),
+        count(|s| matches!(s, BatchStatus::Skipped(_))),
+        count(|s| matches!(s, BatchStatus::Failed(_))),
+    );
+}
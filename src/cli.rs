@@ -2,27 +2,427 @@
 // src/cli.rs - Configuração da interface de linha de comando
 // ============================================================================
 
-use clap::Parser;
+use clap::{ArgAction, Args, Parser, Subcommand};
 use std::path::PathBuf;
 
+use crate::batch::MatchMode;
+use crate::logging::LogFormat;
+use crate::stats::{StatsFormat, StatsTable};
+use crate::server::SERVE_DEFAULT_ADDRESS;
+use tcx_image_overlay::{
+    AppResult, CanvasSize, CollisionPolicy, CropFocus, EncoderOptions, FitMode, FontOptions, FontSpec, FontWeight,
+    IconSet, ImageProcessor, LetterboxFill, Locale, OutputFormat, OutputPreset, OutputTemplate, PngCompression,
+    PresetOptions, SvgFontMode, Theme, TimezoneOverride, Units, WatermarkPolicy, DEFAULT_OUTPUT_QUALITY,
+};
+
 /// Adiciona um overlay de estatísticas de um arquivo TCX ou FIT a uma imagem.
 #[derive(Parser, Debug)]
 #[command(
     author = "Paulo Roberto Torres",
     version = "1.0.0",
     about = "TCX/FIT Image Overlay Tool",
-    long_about = "Adiciona um overlay de estatísticas de treino de arquivos TCX ou FIT a uma imagem."
+    long_about = "Adiciona um overlay de estatísticas de treino de arquivos TCX, FIT ou GPX a uma imagem.",
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
 )]
 pub struct CliArgs {
+    /// Subcomando (sem subcomando, processa uma foto e uma atividade)
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Arquivo de configuração (padrão: ~/.config/tcx_image_overlay/config.toml)
+    #[arg(long = "config", value_name = "ARQUIVO", global = true, help = "Arquivo de configuração TOML")]
+    pub config: Option<PathBuf>,
+
+    /// Perfil nomeado do arquivo de configuração
+    #[arg(long = "profile", value_name = "PERFIL", global = true,
+          help = "Perfil da configuração aplicado sobre os padrões (ex: strava-story)")]
+    pub profile: Option<String>,
+
+    /// Nível de detalhe dos logs
+    #[arg(short = 'v', long = "verbose", action = ArgAction::Count, global = true, conflicts_with = "quiet",
+          help = "Mais detalhes nos logs (-v: depuração, -vv: rastreamento)")]
+    pub verbose: u8,
+
+    /// Silencia as mensagens de progresso
+    #[arg(short = 'q', long = "quiet", global = true, help = "Mostra apenas avisos e erros")]
+    pub quiet: bool,
+
+    /// Formato das linhas de log no stderr
+    #[arg(long = "log-format", value_enum, default_value = "text", global = true,
+          help = "text ou json (um objeto JSON por linha)")]
+    pub log_format: LogFormat,
+
     /// Caminho para a imagem de entrada
-    #[arg(short = 'i', long = "image", value_name = "IMAGEM", help = "Caminho para a imagem")]
-    pub image_path: PathBuf,
+    #[arg(short = 'i', long = "image", value_name = "IMAGEM", help = "Caminho para a imagem",
+          required_unless_present = "sticker")]
+    pub image_path: Option<PathBuf>,
     
-    /// Caminho para o arquivo de atividade (TCX ou FIT)
-    #[arg(short = 'f', long = "file", value_name = "ARQUIVO", help = "Caminho para o arquivo TCX ou FIT")]
-    pub activity_path: PathBuf,
+    /// Caminho para o arquivo de atividade (TCX, FIT ou GPX)
+    #[arg(short = 'f', long = "file", value_name = "ARQUIVO", help = "Caminho para o arquivo TCX, FIT ou GPX",
+          required = true)]
+    pub activity_path: Option<PathBuf>,
+
+    /// Entrada escolhida quando a atividade é um .zip com vários arquivos
+    #[arg(long = "entry", value_name = "NOME", help = "Entrada do .zip com a atividade (ex: 123_ACTIVITY.fit)")]
+    pub entry: Option<String>,
     
     /// Caminho de saída (posicional - opcional)
     #[arg(help = "Caminho para salvar a imagem final (ex: resultado.png)")]
     pub output_path: Option<PathBuf>,
-}
\ No newline at end of file
+
+    #[command(flatten)]
+    pub overlay: OverlayArgs,
+
+    /// Modo adesivo: renderiza só o overlay em uma tela transparente (PNG com alfa)
+    #[arg(long = "sticker", value_name = "LARGURAxALTURA", conflicts_with = "image_path",
+          help = "Gera apenas o overlay em PNG transparente do tamanho informado (ex: 1080x1920)")]
+    pub sticker: Option<CanvasSize>,
+}
+
+/// Subcomandos disponíveis
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Processa várias fotos, associando cada uma à atividade em que foi tirada
+    Batch(BatchArgs),
+    /// Observa diretórios e processa automaticamente fotos e atividades novas
+    Watch(WatchArgs),
+    /// Servidor HTTP local com formulário de upload e API JSON
+    Serve(ServeArgs),
+    /// Exporta as estatísticas da atividade em JSON ou CSV, sem gerar imagem
+    Stats(StatsArgs),
+}
+
+/// Argumentos do modo batch
+#[derive(Args, Debug)]
+pub struct BatchArgs {
+    /// Diretórios, arquivos ou padrões glob das fotos
+    #[arg(short = 'p', long = "photos", value_name = "FOTOS", num_args = 1.., required = true,
+          help = "Diretórios, arquivos ou globs das fotos (ex: fotos/ 'camera/*.jpg')")]
+    pub photos: Vec<String>,
+
+    /// Diretórios, arquivos ou padrões glob das atividades
+    #[arg(short = 'a', long = "activities", value_name = "ATIVIDADES", num_args = 1.., required = true,
+          help = "Diretórios, arquivos ou globs das atividades TCX/FIT/GPX")]
+    pub activities: Vec<String>,
+
+    /// Diretório de saída (padrão: ~/stats_overlay/YYYY-MM-DD/)
+    #[arg(short = 'o', long = "output-dir", value_name = "DIRETÓRIO", help = "Diretório onde salvar as imagens")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Critério de associação entre foto e atividade
+    #[arg(long = "match", value_enum, default_value = "auto",
+          help = "exif: horário da foto; filename: nome do arquivo; auto: EXIF e depois nome")]
+    pub match_mode: MatchMode,
+
+    /// Número de fotos processadas em paralelo
+    #[arg(short = 'j', long = "jobs", value_name = "N", help = "Fotos processadas em paralelo (padrão: nº de CPUs)")]
+    pub jobs: Option<usize>,
+
+    #[command(flatten)]
+    pub overlay: OverlayArgs,
+}
+
+/// Argumentos do modo watch
+#[derive(Args, Debug)]
+pub struct WatchArgs {
+    /// Diretórios observados (ex: exportação do Garmin e pasta de fotos do celular)
+    #[arg(value_name = "DIRETÓRIOS", required = true, help = "Diretórios com fotos e/ou atividades a observar")]
+    pub directories: Vec<PathBuf>,
+
+    /// Tempo sem novos eventos antes de processar (arquivos ainda sendo copiados)
+    #[arg(long = "debounce", value_name = "MS", default_value_t = 2000, help = "Espera (ms) após o último evento")]
+    pub debounce_ms: u64,
+
+    /// Arquivo de estado com as fotos já processadas
+    #[arg(long = "state-file", value_name = "ARQUIVO", help = "Padrão: ~/stats_overlay/.watch_state")]
+    pub state_file: Option<PathBuf>,
+
+    /// Processa também as fotos que já estão nos diretórios ao iniciar
+    #[arg(long = "initial-scan", help = "Processa as fotos já existentes ainda não registradas no estado")]
+    pub initial_scan: bool,
+
+    /// Diretório de saída (padrão: ~/stats_overlay/YYYY-MM-DD/)
+    #[arg(short = 'o', long = "output-dir", value_name = "DIRETÓRIO", help = "Diretório onde salvar as imagens")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Critério de associação entre foto e atividade
+    #[arg(long = "match", value_enum, default_value = "auto",
+          help = "exif: horário da foto; filename: nome do arquivo; auto: EXIF e depois nome")]
+    pub match_mode: MatchMode,
+
+    #[command(flatten)]
+    pub overlay: OverlayArgs,
+}
+
+/// Argumentos do modo serve
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    /// Endereço e porta (padrão: apenas local)
+    #[arg(long = "bind", value_name = "ENDEREÇO", default_value = SERVE_DEFAULT_ADDRESS,
+          help = "Endereço de escuta (ex: 127.0.0.1:8080; 0.0.0.0 expõe na rede)")]
+    pub bind: String,
+
+    /// Requisições atendidas em paralelo
+    #[arg(short = 'j', long = "jobs", value_name = "N", help = "Requisições em paralelo (padrão: nº de CPUs)")]
+    pub jobs: Option<usize>,
+}
+
+/// Argumentos da exportação de estatísticas
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    /// Arquivo de atividade (TCX, FIT ou GPX, também .gz/.zip; `-` lê da entrada padrão)
+    #[arg(value_name = "ARQUIVO", help = "Arquivo TCX, FIT ou GPX (- para a entrada padrão)")]
+    pub activity_path: PathBuf,
+
+    /// Entrada escolhida quando a atividade é um .zip com vários arquivos
+    #[arg(long = "entry", value_name = "NOME", help = "Entrada do .zip com a atividade (ex: 123_ACTIVITY.fit)")]
+    pub entry: Option<String>,
+
+    /// Formato da exportação
+    #[arg(long = "format", value_enum, default_value = "json", help = "json ou csv")]
+    pub format: StatsFormat,
+
+    /// Tabela exportada no formato CSV
+    #[arg(long = "table", value_enum, default_value = "summary", help = "Tabela do CSV: summary, laps, zones, power-zones ou samples")]
+    pub table: StatsTable,
+
+    /// Inclui as amostras (série temporal) no JSON
+    #[arg(long = "samples", help = "Inclui as amostras no JSON")]
+    pub samples: bool,
+
+    /// FC máxima usada nas zonas (padrão: a maior FC da atividade)
+    #[arg(long = "max-hr", value_name = "BPM", help = "FC máxima para as zonas de FC")]
+    pub max_hr: Option<u8>,
+
+    /// Limites inferiores das zonas de FC em % da FC máxima
+    #[arg(long = "hr-zones", value_name = "PERCENTUAIS", value_delimiter = ',',
+          help = "Início de cada zona em % da FC máxima (padrão: 50,60,70,80,90)")]
+    pub hr_zones: Option<Vec<u8>>,
+
+    /// FTP (potência funcional de limiar) usado nas zonas de potência
+    #[arg(long = "ftp", value_name = "WATTS", help = "FTP para as zonas de potência (sem ele, as zonas não são calculadas)")]
+    pub ftp: Option<u16>,
+
+    /// Fuso horário do horário local de início
+    #[arg(long = "timezone", value_name = "FUSO", help = "Fuso horário da atividade (ex: America/Sao_Paulo, -03:00)")]
+    pub timezone: Option<TimezoneOverride>,
+}
+
+/// Opções do overlay compartilhadas entre o modo simples e o batch
+#[derive(Args, Debug, Clone)]
+pub struct OverlayArgs {
+    /// Fuso horário para exibir o horário da atividade (sobrepõe o detectado)
+    #[arg(long = "timezone", value_name = "FUSO", help = "Fuso horário da atividade (ex: America/Sao_Paulo, -03:00)")]
+    pub timezone: Option<TimezoneOverride>,
+
+    /// Adiciona as estatísticas do momento em que a foto foi tirada (EXIF)
+    #[arg(long = "at-photo", overrides_with = "no_at_photo",
+          help = "Mostra distância, tempo, FC e altitude no momento da foto (EXIF)")]
+    pub at_photo: bool,
+
+    /// Desativa `at-photo` ligado na configuração
+    #[arg(long = "no-at-photo", overrides_with = "at_photo", help = "Não mostra as estatísticas do momento da foto")]
+    pub no_at_photo: bool,
+
+    /// Preserva EXIF (data de captura, câmera, GPS) e perfil ICC da foto original
+    #[arg(long = "keep-metadata", overrides_with = "no_keep_metadata",
+          help = "Copia EXIF e perfil de cor ICC da foto original para a saída")]
+    pub keep_metadata: bool,
+
+    /// Desativa `keep-metadata` ligado na configuração
+    #[arg(long = "no-keep-metadata", overrides_with = "keep_metadata", help = "Não copia os metadados da foto original")]
+    pub no_keep_metadata: bool,
+
+    /// Remove as coordenadas GPS do EXIF preservado
+    #[arg(long = "strip-gps", overrides_with = "no_strip_gps",
+          help = "Remove a localização GPS dos metadados preservados (privacidade)")]
+    pub strip_gps: bool,
+
+    /// Desativa `strip-gps` ligado na configuração
+    #[arg(long = "no-strip-gps", overrides_with = "strip_gps", help = "Mantém a localização GPS dos metadados preservados")]
+    pub no_strip_gps: bool,
+
+    /// Grava o resumo da atividade nos metadados da imagem
+    #[arg(long = "describe", overrides_with = "no_describe",
+          help = "Grava o resumo da atividade em EXIF ImageDescription e XMP")]
+    pub describe: bool,
+
+    /// Desativa `describe` ligado na configuração
+    #[arg(long = "no-describe", overrides_with = "describe", help = "Não grava o resumo da atividade nos metadados")]
+    pub no_describe: bool,
+
+    /// Formato de saída (independente da extensão do arquivo)
+    #[arg(long = "format", value_enum, value_name = "FORMATO", help = "Formato da imagem final (padrão: deduzido da extensão)")]
+    pub format: Option<OutputFormat>,
+
+    /// Qualidade para JPEG e WebP com perdas
+    #[arg(long = "quality", value_name = "1-100", default_value_t = DEFAULT_OUTPUT_QUALITY,
+          value_parser = clap::value_parser!(u8).range(1..=100), help = "Qualidade de JPEG/WebP com perdas")]
+    pub quality: u8,
+
+    /// Nível de compressão do PNG
+    #[arg(long = "png-compression", value_enum, value_name = "NÍVEL", default_value = "default", help = "Compressão do PNG")]
+    pub png_compression: PngCompression,
+
+    /// Gera JPEG progressivo
+    #[arg(long = "progressive", overrides_with = "no_progressive", help = "Gera JPEG progressivo")]
+    pub progressive: bool,
+
+    /// Desativa `progressive` ligado na configuração
+    #[arg(long = "no-progressive", overrides_with = "progressive", help = "Gera JPEG sequencial (baseline)")]
+    pub no_progressive: bool,
+
+    /// Preset de rede social aplicado antes do overlay
+    #[arg(long = "preset", value_enum, value_name = "PRESET", help = "Recorta/redimensiona para redes sociais")]
+    pub preset: Option<OutputPreset>,
+
+    /// Como encaixar a foto na proporção do preset
+    #[arg(long = "fit", value_enum, default_value = "crop", help = "crop: recorta o excesso; pad: letterbox")]
+    pub fit: FitMode,
+
+    /// Região preservada ao recortar
+    #[arg(long = "crop-focus", value_enum, default_value = "center", help = "center ou smart (região com mais detalhes)")]
+    pub crop_focus: CropFocus,
+
+    /// Preenchimento das bordas no modo pad
+    #[arg(long = "fill", value_name = "PREENCHIMENTO", default_value = "blur", help = "blur ou cor sólida (ex: #202020)")]
+    pub fill: LetterboxFill,
+
+    /// Modelo do caminho da saída automática
+    #[arg(long = "output-template", value_name = "MODELO",
+          help = "Modelo da saída automática (ex: '{home}/overlays/{activity_date:%Y/%m}/{sport}-{photo_stem}.{ext}')")]
+    pub output_template: Option<OutputTemplate>,
+
+    /// O que fazer quando o arquivo de saída já existe
+    #[arg(long = "on-conflict", value_enum, default_value = "overwrite",
+          help = "overwrite: substitui; suffix: acrescenta -1, -2...; skip: não grava; error: falha")]
+    pub on_conflict: CollisionPolicy,
+
+    /// Nunca substitui arquivos existentes
+    #[arg(long = "no-clobber", conflicts_with_all = ["force", "on_conflict"],
+          help = "Falha se o arquivo de saída já existir (equivale a --on-conflict error)")]
+    pub no_clobber: bool,
+
+    /// Substitui arquivos existentes mesmo com on-conflict na configuração
+    #[arg(long = "force", conflicts_with = "on_conflict",
+          help = "Substitui o arquivo de saída se já existir (equivale a --on-conflict overwrite)")]
+    pub force: bool,
+
+    /// Fonte dos rótulos e unidades: arquivo ou família instalada
+    #[arg(long = "font", value_name = "FONTE",
+          help = "Arquivo .ttf/.otf/.ttc ou família instalada (ex: 'Noto Sans', ~/fontes/Inter.ttf)")]
+    pub font: Option<FontSpec>,
+
+    /// Fonte dos valores numéricos (padrão: a mesma de --font)
+    #[arg(long = "number-font", value_name = "FONTE", help = "Fonte dos números (padrão: a de --font)")]
+    pub number_font: Option<FontSpec>,
+
+    /// Peso da fonte dos rótulos
+    #[arg(long = "font-weight", value_enum, default_value = "regular", help = "Peso dos rótulos: regular ou bold")]
+    pub font_weight: FontWeight,
+
+    /// Peso da fonte dos números
+    #[arg(long = "number-weight", value_enum, default_value = "regular", help = "Peso dos números: regular ou bold")]
+    pub number_weight: FontWeight,
+
+    /// Conjunto de ícones: diretório com `<ícone>.svg`/`.png` e `icons.toml` opcional
+    #[arg(long = "icons", value_name = "CONJUNTO",
+          help = "Diretório (ou icons.toml) com ícones SVG/PNG e/ou uma fonte de ícones")]
+    pub icons: Option<PathBuf>,
+
+    /// Como as fontes são incluídas na saída SVG
+    #[arg(long = "svg-fonts", value_enum, default_value = "embed",
+          help = "embed: fontes em base64 no SVG; reference: nome e caminho local das fontes")]
+    pub svg_fonts: SvgFontMode,
+
+    /// Cores do texto e da sombra
+    #[arg(long = "theme", value_enum, default_value = "light",
+          help = "light: texto claro; dark: texto escuro (fotos claras); auto: pelo fundo")]
+    pub theme: Theme,
+
+    /// Marca d'água dos dispositivos Garmin
+    #[arg(long = "watermark", value_enum, default_value = "auto",
+          help = "auto: cor pelo fundo; white ou black: cor fixa; none: sem marca d'água")]
+    pub watermark: WatermarkPolicy,
+
+    /// Unidades de distância, ritmo, velocidade e altitude
+    #[arg(long = "units", value_enum, default_value = "metric", help = "metric (km, m) ou imperial (mi, ft)")]
+    pub units: Units,
+
+    /// Formato regional da data e da hora
+    #[arg(long = "locale", value_enum, default_value = "pt-br",
+          help = "pt-br ou en-gb (20/08/2024 06:00), en-us (08/20/2024 6:00 AM)")]
+    pub locale: Locale,
+
+    /// Mostra as decisões de layout do overlay
+    #[arg(long = "explain", help = "Imprime posições, luminância do fundo e escala da fonte em JSON no stderr")]
+    pub explain: bool,
+}
+
+impl OverlayArgs {
+    /// Aplica as opções ao processador (preset, fuso, metadados, codificador, fontes e ícones)
+    pub fn configure(&self, processor: &mut ImageProcessor) -> AppResult<()> {
+        // Preset de rede social: recorta/redimensiona antes do layout do overlay
+        if let Some(preset) = self.preset {
+            processor.apply_preset(&PresetOptions {
+                preset,
+                fit: self.fit,
+                focus: self.crop_focus,
+                fill: self.fill,
+            });
+        }
+
+        if let Some(timezone) = self.timezone {
+            processor.set_timezone(timezone);
+        }
+        processor.set_photo_point(self.at_photo);
+        processor.set_metadata_options(self.keep_metadata, self.strip_gps, self.describe);
+        processor.set_encoder_options(EncoderOptions {
+            format: self.format,
+            quality: self.quality,
+            png_compression: self.png_compression,
+            progressive: self.progressive,
+        });
+        processor.set_svg_font_mode(self.svg_fonts);
+        processor.set_theme(self.theme);
+        processor.set_watermark_policy(self.watermark);
+        processor.set_units(self.units);
+        processor.set_locale(self.locale);
+        if let Some(template) = &self.output_template {
+            processor.set_output_template(template.clone());
+        }
+        processor.set_collision_policy(self.collision_policy());
+
+        // As fontes padrão já vêm carregadas
+        let fonts = self.font_options();
+        if fonts != FontOptions::default() {
+            processor.set_fonts(&fonts)?;
+        }
+        if let Some(icons) = &self.icons {
+            processor.set_icons(IconSet::load(icons)?);
+        }
+        Ok(())
+    }
+
+    /// Fontes escolhidas para rótulos e números
+    pub fn font_options(&self) -> FontOptions {
+        FontOptions {
+            label: self.font.clone(),
+            label_weight: self.font_weight,
+            number: self.number_font.clone(),
+            number_weight: self.number_weight,
+        }
+    }
+
+    /// Política de colisão efetiva: --no-clobber e --force têm prioridade
+    pub fn collision_policy(&self) -> CollisionPolicy {
+        if self.no_clobber {
+            CollisionPolicy::Error
+        } else if self.force {
+            CollisionPolicy::Overwrite
+        } else {
+            self.on_conflict
+        }
+    }
+}
@@ -0,0 +1,298 @@
+// ============================================================================
+// src/config.rs - Arquivo de configuração com padrões do usuário e perfis
+// ============================================================================
+
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use serde::Deserialize;
+use log::info;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::OverlayArgs;
+use tcx_image_overlay::{AppError, AppResult, TimezoneOverride, CONFIG_DIR_NAME};
+
+/// Nome do arquivo dentro de ~/.config/tcx_image_overlay
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Conteúdo do `config.toml`: padrões em `[defaults]` e perfis em `[profiles.<nome>]`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    defaults: ConfigOptions,
+    #[serde(default)]
+    profiles: BTreeMap<String, ConfigOptions>,
+}
+
+/// Opções aceitas no arquivo, com os mesmos nomes das flags da CLI.
+/// Valores ausentes mantêm o padrão da CLI.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigOptions {
+    output_dir: Option<PathBuf>,
+    /// Diretório com `fonts/` e `img/` que substituem os recursos embutidos
+    assets_dir: Option<PathBuf>,
+    timezone: Option<String>,
+    at_photo: Option<bool>,
+    keep_metadata: Option<bool>,
+    strip_gps: Option<bool>,
+    describe: Option<bool>,
+    format: Option<String>,
+    quality: Option<u8>,
+    png_compression: Option<String>,
+    progressive: Option<bool>,
+    preset: Option<String>,
+    fit: Option<String>,
+    crop_focus: Option<String>,
+    fill: Option<String>,
+    svg_fonts: Option<String>,
+    /// Arquivo ou família instalada (`--font`)
+    font: Option<String>,
+    number_font: Option<String>,
+    font_weight: Option<String>,
+    number_weight: Option<String>,
+    /// Diretório ou `icons.toml` do conjunto de ícones (`--icons`)
+    icons: Option<PathBuf>,
+    output_template: Option<String>,
+    on_conflict: Option<String>,
+    /// Cores do overlay: light, dark ou auto (`--theme`)
+    theme: Option<String>,
+    /// Política da marca d'água: auto, white, black ou none (`--watermark`)
+    watermark: Option<String>,
+    /// Unidades: metric ou imperial (`--units`)
+    units: Option<String>,
+    /// Formato da data e da hora: pt-br, en-us ou en-gb (`--locale`)
+    locale: Option<String>,
+    /// FC máxima usada nas zonas de FC do `stats`
+    max_hr: Option<u8>,
+    /// Limites inferiores das zonas de FC em % da FC máxima
+    hr_zones: Option<Vec<u8>>,
+    /// FTP em watts usado nas zonas de potência do `stats`
+    ftp: Option<u16>,
+}
+
+/// Caminho padrão: ~/.config/tcx_image_overlay/config.toml (diretório de
+/// configuração do sistema em macOS/Windows)
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+}
+
+/// Carrega a configuração e aplica o perfil escolhido sobre os padrões.
+/// Sem `--config`, a ausência do arquivo padrão não é erro.
+pub fn load(path: Option<&Path>, profile: Option<&str>) -> AppResult<ConfigOptions> {
+    let (path, explicit) = match path {
+        Some(path) => (Some(path.to_path_buf()), true),
+        None => (default_path(), false),
+    };
+
+    let file = match path.as_deref().map(fs::read_to_string) {
+        Some(Ok(contents)) => {
+            let path = path.as_deref().unwrap_or(Path::new(""));
+            info!("⚙️  Configuração: {:?}", path);
+            toml::from_str::<ConfigFile>(&contents)
+                .map_err(|e| AppError::InvalidFormat(format!("Configuração inválida em {:?}: {}", path, e)))?
+        }
+        Some(Err(e)) if explicit || e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(AppError::InvalidFormat(format!("Falha ao ler a configuração {:?}: {}", path.unwrap_or_default(), e)));
+        }
+        _ => ConfigFile::default(),
+    };
+
+    let Some(profile) = profile else {
+        return Ok(file.defaults);
+    };
+    let selected = file.profiles.get(profile).ok_or_else(|| {
+        let available: Vec<&str> = file.profiles.keys().map(String::as_str).collect();
+        AppError::InvalidFormat(format!(
+            "Perfil '{}' não encontrado (disponíveis: {})",
+            profile,
+            if available.is_empty() { "nenhum".to_string() } else { available.join(", ") }
+        ))
+    })?;
+    info!("⚙️  Perfil: {}", profile);
+    Ok(file.defaults.merged_with(selected))
+}
+
+impl ConfigOptions {
+    /// Valores do perfil têm prioridade sobre os padrões
+    fn merged_with(self, profile: &ConfigOptions) -> ConfigOptions {
+        let profile = profile.clone();
+        ConfigOptions {
+            output_dir: profile.output_dir.or(self.output_dir),
+            assets_dir: profile.assets_dir.or(self.assets_dir),
+            timezone: profile.timezone.or(self.timezone),
+            at_photo: profile.at_photo.or(self.at_photo),
+            keep_metadata: profile.keep_metadata.or(self.keep_metadata),
+            strip_gps: profile.strip_gps.or(self.strip_gps),
+            describe: profile.describe.or(self.describe),
+            format: profile.format.or(self.format),
+            quality: profile.quality.or(self.quality),
+            png_compression: profile.png_compression.or(self.png_compression),
+            progressive: profile.progressive.or(self.progressive),
+            preset: profile.preset.or(self.preset),
+            fit: profile.fit.or(self.fit),
+            crop_focus: profile.crop_focus.or(self.crop_focus),
+            fill: profile.fill.or(self.fill),
+            svg_fonts: profile.svg_fonts.or(self.svg_fonts),
+            font: profile.font.or(self.font),
+            number_font: profile.number_font.or(self.number_font),
+            font_weight: profile.font_weight.or(self.font_weight),
+            number_weight: profile.number_weight.or(self.number_weight),
+            icons: profile.icons.or(self.icons),
+            output_template: profile.output_template.or(self.output_template),
+            on_conflict: profile.on_conflict.or(self.on_conflict),
+            theme: profile.theme.or(self.theme),
+            watermark: profile.watermark.or(self.watermark),
+            units: profile.units.or(self.units),
+            locale: profile.locale.or(self.locale),
+            max_hr: profile.max_hr.or(self.max_hr),
+            hr_zones: profile.hr_zones.or(self.hr_zones),
+            ftp: profile.ftp.or(self.ftp),
+        }
+    }
+
+    /// Diretório de saída, com `~` expandido para o diretório home
+    pub fn output_dir(&self) -> Option<PathBuf> {
+        self.output_dir.as_deref().map(expand_home)
+    }
+
+    /// Diretório de recursos (fontes e marcas d'água), com `~` expandido
+    pub fn assets_dir(&self) -> Option<PathBuf> {
+        self.assets_dir.as_deref().map(expand_home)
+    }
+
+    pub fn timezone(&self) -> AppResult<Option<TimezoneOverride>> {
+        self.timezone.as_deref().map(str::parse).transpose().map_err(|e| invalid("timezone", e))
+    }
+
+    pub fn max_hr(&self) -> Option<u8> {
+        self.max_hr
+    }
+
+    pub fn hr_zones(&self) -> Option<&[u8]> {
+        self.hr_zones.as_deref()
+    }
+
+    pub fn ftp(&self) -> Option<u16> {
+        self.ftp
+    }
+
+    /// Aplica os valores às opções do overlay. Flags passadas na linha de
+    /// comando (segundo `matches`) sempre prevalecem sobre o arquivo.
+    pub fn apply_overlay(&self, overlay: &mut OverlayArgs, matches: &ArgMatches) -> AppResult<()> {
+        let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        if overlay.timezone.is_none() {
+            overlay.timezone = self.timezone()?;
+        }
+        if let (None, Some(format)) = (overlay.format, &self.format) {
+            overlay.format = Some(parse_enum("format", format)?);
+        }
+        if let (None, Some(preset)) = (overlay.preset, &self.preset) {
+            overlay.preset = Some(parse_enum("preset", preset)?);
+        }
+        if let (None, Some(font)) = (&overlay.font, &self.font) {
+            overlay.font = Some(font.parse().map_err(|e| invalid("font", e))?);
+        }
+        if let (None, Some(font)) = (&overlay.number_font, &self.number_font) {
+            overlay.number_font = Some(font.parse().map_err(|e| invalid("number-font", e))?);
+        }
+        if let (None, Some(icons)) = (&overlay.icons, &self.icons) {
+            overlay.icons = Some(expand_home(icons));
+        }
+        if let (None, Some(template)) = (&overlay.output_template, &self.output_template) {
+            overlay.output_template = Some(template.parse().map_err(|e| invalid("output-template", e))?);
+        }
+
+        // Flags booleanas: `--x`/`--no-x` da linha de comando (a última vence),
+        // senão o arquivo, senão desligadas
+        let cli_flag = |id: &str, enabled: bool, negation: &str, disabled: bool| {
+            (from_cli(id) || from_cli(negation)).then_some(enabled && !disabled)
+        };
+        overlay.at_photo = cli_flag("at_photo", overlay.at_photo, "no_at_photo", overlay.no_at_photo)
+            .or(self.at_photo)
+            .unwrap_or(false);
+        overlay.keep_metadata = cli_flag("keep_metadata", overlay.keep_metadata, "no_keep_metadata", overlay.no_keep_metadata)
+            .or(self.keep_metadata)
+            .unwrap_or(false);
+        overlay.strip_gps = cli_flag("strip_gps", overlay.strip_gps, "no_strip_gps", overlay.no_strip_gps)
+            .or(self.strip_gps)
+            .unwrap_or(false);
+        overlay.describe = cli_flag("describe", overlay.describe, "no_describe", overlay.no_describe)
+            .or(self.describe)
+            .unwrap_or(false);
+        overlay.progressive = cli_flag("progressive", overlay.progressive, "no_progressive", overlay.no_progressive)
+            .or(self.progressive)
+            .unwrap_or(false);
+
+        // Opções com valor padrão na CLI: o arquivo só substitui o padrão
+        if let (false, Some(quality)) = (from_cli("quality"), self.quality) {
+            if !(1..=100).contains(&quality) {
+                return Err(invalid("quality", "deve estar entre 1 e 100".to_string()));
+            }
+            overlay.quality = quality;
+        }
+        if let (false, Some(value)) = (from_cli("png_compression"), &self.png_compression) {
+            overlay.png_compression = parse_enum("png-compression", value)?;
+        }
+        if let (false, Some(value)) = (from_cli("fit"), &self.fit) {
+            overlay.fit = parse_enum("fit", value)?;
+        }
+        if let (false, Some(value)) = (from_cli("crop_focus"), &self.crop_focus) {
+            overlay.crop_focus = parse_enum("crop-focus", value)?;
+        }
+        if let (false, Some(value)) = (from_cli("fill"), &self.fill) {
+            overlay.fill = value.parse().map_err(|e| invalid("fill", e))?;
+        }
+        if let (false, Some(value)) = (from_cli("svg_fonts"), &self.svg_fonts) {
+            overlay.svg_fonts = parse_enum("svg-fonts", value)?;
+        }
+        if let (false, Some(value)) = (from_cli("font_weight"), &self.font_weight) {
+            overlay.font_weight = parse_enum("font-weight", value)?;
+        }
+        if let (false, Some(value)) = (from_cli("number_weight"), &self.number_weight) {
+            overlay.number_weight = parse_enum("number-weight", value)?;
+        }
+        if let (false, Some(value)) = (from_cli("on_conflict"), &self.on_conflict) {
+            overlay.on_conflict = parse_enum("on-conflict", value)?;
+        }
+        if let (false, Some(value)) = (from_cli("theme"), &self.theme) {
+            overlay.theme = parse_enum("theme", value)?;
+        }
+        if let (false, Some(value)) = (from_cli("watermark"), &self.watermark) {
+            overlay.watermark = parse_enum("watermark", value)?;
+        }
+        if let (false, Some(value)) = (from_cli("units"), &self.units) {
+            overlay.units = parse_enum("units", value)?;
+        }
+        if let (false, Some(value)) = (from_cli("locale"), &self.locale) {
+            overlay.locale = parse_enum("locale", value)?;
+        }
+        Ok(())
+    }
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn invalid(key: &str, message: String) -> AppError {
+    AppError::InvalidFormat(format!("Configuração '{}': {}", key, message))
+}
+
+/// Valor de um enum da CLI pelo nome usado nas flags (ex: "webp-lossy")
+fn parse_enum<T: ValueEnum>(key: &str, value: &str) -> AppResult<T> {
+    T::from_str(value, true).map_err(|_| {
+        let names: Vec<String> = T::value_variants()
+            .iter()
+            .filter_map(|variant| variant.to_possible_value())
+            .map(|possible| possible.get_name().to_string())
+            .collect();
+        invalid(key, format!("valor inválido '{}' (use {})", value, names.join(", ")))
+    })
+}
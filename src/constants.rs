@@ -4,31 +4,97 @@
 
 use image::Rgba;
 
-// Caminhos de arquivos
-pub const FONT_PATH: &str = "fonts/DejaVuSans.ttf";
-pub const ICON_FONT_PATH: &str = "fonts/FontAwesome.ttf";
+// Caminhos de arquivos (fontes e marcas d'água ficam em `assets::Asset`)
 pub const DEFAULT_OUTPUT_PATH: &str = "resultado_com_overlay.png";
-pub const WATERMARK_WHITE_PATH: &str = "img/garmin_white.png";
-pub const WATERMARK_BLACK_PATH: &str = "img/garmin_black.png";
+// Modelo da saída automática (ver `output_path::OutputTemplate` para os placeholders)
+pub const DEFAULT_OUTPUT_TEMPLATE: &str = "{home}/stats_overlay/{activity_date}/{photo_stem}-{kind}.{ext}";
+// Caminho especial para entrada/saída padrão (`-i -`, `-f -`, saída `-`)
+pub const STDIO_PATH: &str = "-";
+
+// Maior largura/altura aceita para a tela do modo adesivo (--sticker)
+pub const MAX_CANVAS_DIMENSION: u32 = 16384;
+// Maior largura/altura que o codificador WebP aceita
+pub const WEBP_MAX_DIMENSION: u32 = 16383;
+
+// Menor tamanho de fonte (px) ao reduzir o overlay para caber em imagens pequenas
+pub const MIN_FONT_SCALE: f32 = 8.0;
+
+// Qualidade padrão para JPEG e WebP com perdas
+pub const DEFAULT_OUTPUT_QUALITY: u8 = 90;
 
 // Cores para diferentes tipos de dados
 pub const TEXT_COLOR: Rgba<u8> = Rgba([255u8, 255u8, 255u8, 255u8]);
 pub const SHADOW_COLOR: Rgba<u8> = Rgba([0u8, 0u8, 0u8, 255u8]);
+pub const SUBTEXT_COLOR: Rgba<u8> = Rgba([180u8, 180u8, 180u8, 255u8]);
+// Tema dark: texto escuro com sombra clara
+pub const DARK_TEXT_COLOR: Rgba<u8> = Rgba([32u8, 32u8, 32u8, 255u8]);
+pub const LIGHT_SHADOW_COLOR: Rgba<u8> = Rgba([255u8, 255u8, 255u8, 255u8]);
+pub const DARK_SUBTEXT_COLOR: Rgba<u8> = Rgba([80u8, 80u8, 80u8, 255u8]);
 pub const TIME_COLOR: Rgba<u8> = Rgba([52u8, 152u8, 219u8, 255u8]);
 pub const CALORIES_COLOR: Rgba<u8> = Rgba([230u8, 126u8, 34u8, 255u8]);
 pub const HR_COLOR: Rgba<u8> = Rgba([231u8, 76u8, 60u8, 255u8]);
 pub const DATE_COLOR: Rgba<u8> = Rgba([46u8, 204u8, 113u8, 255u8]);
 pub const DEVICE_COLOR: Rgba<u8> = Rgba([149u8, 165u8, 166u8, 255u8]);
+pub const MOVING_COLOR: Rgba<u8> = Rgba([26u8, 188u8, 156u8, 255u8]);
+pub const PACE_COLOR: Rgba<u8> = Rgba([155u8, 89u8, 182u8, 255u8]);
+pub const PHOTO_COLOR: Rgba<u8> = Rgba([241u8, 196u8, 15u8, 255u8]);
+pub const SPORT_COLOR: Rgba<u8> = Rgba([236u8, 240u8, 241u8, 255u8]);
+pub const DISTANCE_COLOR: Rgba<u8> = Rgba([52u8, 73u8, 219u8, 255u8]);
+pub const ELEVATION_COLOR: Rgba<u8> = Rgba([139u8, 195u8, 74u8, 255u8]);
+pub const POWER_COLOR: Rgba<u8> = Rgba([255u8, 193u8, 7u8, 255u8]);
+pub const CADENCE_COLOR: Rgba<u8> = Rgba([233u8, 30u8, 99u8, 255u8]);
 
-// Ícones FontAwesome (Unicode)
+// Famílias tentadas primeiro quando a fonte escolhida não tem um caractere
+// (nomes de dispositivos e lugares com acentos, CJK...)
+pub const FALLBACK_FONT_FAMILIES: &[&str] = &[
+    "Noto Sans", "Noto Sans CJK SC", "Noto Sans CJK JP", "Noto Sans CJK KR", "Source Han Sans",
+    "WenQuanYi Micro Hei", "Droid Sans Fallback", "Arial Unicode MS", "Microsoft YaHei", "Hiragino Sans",
+    "PingFang SC", "Apple SD Gothic Neo", "Noto Sans Symbols", "DejaVu Sans",
+];
+
+// Ícones do FontAwesome embutido (Unicode); conjuntos de ícones podem substituí-los
 pub const ICON_TIME: &str = "\u{f017}";
 pub const ICON_FIRE: &str = "\u{f06d}";
 pub const ICON_HEART: &str = "\u{f21e}";
 pub const ICON_CALENDAR: &str = "\u{f133}";
 pub const ICON_DEVICE: &str = "\u{f10b}";
+pub const ICON_MOVING: &str = "\u{f04b}";
+pub const ICON_PACE: &str = "\u{f0e4}";
+pub const ICON_CAMERA: &str = "\u{f030}";
+pub const ICON_DISTANCE: &str = "\u{f4d7}";
+pub const ICON_ELEVATION: &str = "\u{f6fc}";
+pub const ICON_POWER: &str = "\u{f0e7}";
+pub const ICON_CADENCE: &str = "\u{f021}";
+pub const ICON_RUNNING: &str = "\u{f70c}";
+pub const ICON_CYCLING: &str = "\u{f206}";
+pub const ICON_SWIMMING: &str = "\u{f5c4}";
+pub const ICON_WALKING: &str = "\u{f554}";
+pub const ICON_HIKING: &str = "\u{f6ec}";
+pub const ICON_OTHER_SPORT: &str = "\u{f2f2}";
+
+// Arquivo opcional de um conjunto de ícones (fonte, mapa de glifos, tint)
+pub const ICON_SET_MANIFEST: &str = "icons.toml";
+
+// Velocidade mínima (m/s) para considerar um intervalo como "em movimento"
+pub const MOVING_SPEED_THRESHOLD_MPS: f64 = 0.5;
+
+// Subida mínima (m) acima do último ponto baixo para contar no ganho de elevação,
+// filtrando o ruído do altímetro/GPS
+pub const ELEVATION_GAIN_THRESHOLD_METERS: f64 = 2.0;
+
+// Limite inferior de cada zona de FC (Z1..Z5) em % da FC máxima
+pub const HR_ZONE_LOWER_PERCENTS: [u8; 5] = [50, 60, 70, 80, 90];
+
+// Limite inferior de cada zona de potência (Z1..Z7, modelo de Coggan) em % do FTP
+pub const POWER_ZONE_LOWER_PERCENTS: [u8; 7] = [0, 56, 76, 91, 106, 121, 151];
 
 // Séries de dispositivos Garmin para detecção
 pub const GARMIN_SERIES: &[&str] = &[
     "forerunner", "fenix", "venu", "vivoactive", "instinct",
     "epix", "enduro", "approach", "marq", "lily", "tactix", "descent", "garmin"
-];
\ No newline at end of file
+];
+
+// Diretório da aplicação em ~/.config (configuração) e ~/.local/share (recursos)
+pub const CONFIG_DIR_NAME: &str = "tcx_image_overlay";
+
+
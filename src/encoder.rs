@@ -0,0 +1,178 @@
+// ============================================================================
+// src/encoder.rs - Codificação da imagem final (JPEG, PNG, WebP)
+// ============================================================================
+
+use clap::ValueEnum;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ImageEncoder, ImageFormat, RgbaImage};
+use log::debug;
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::constants::{DEFAULT_OUTPUT_QUALITY, WEBP_MAX_DIMENSION};
+use crate::error::{AppError, AppResult};
+
+/// Formato de saída escolhido com `--format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// JPEG (usa --quality e --progressive)
+    Jpeg,
+    /// PNG (usa --png-compression)
+    Png,
+    /// WebP sem perdas
+    Webp,
+    /// WebP com perdas (usa --quality)
+    WebpLossy,
+    /// SVG vetorial: texto e ícones editáveis sobre a foto embutida
+    Svg,
+}
+
+impl OutputFormat {
+    /// Deduz o formato pela extensão do arquivo, se for um dos formatos gerenciados
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "jpg" | "jpeg" => Some(OutputFormat::Jpeg),
+            "png" => Some(OutputFormat::Png),
+            "webp" => Some(OutputFormat::Webp),
+            "svg" => Some(OutputFormat::Svg),
+            _ => None,
+        }
+    }
+
+    /// Extensão de arquivo padrão do formato
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Png => "png",
+            OutputFormat::Webp | OutputFormat::WebpLossy => "webp",
+            OutputFormat::Svg => "svg",
+        }
+    }
+
+    /// Indica se o formato preserva o canal alfa (exigido pelo modo adesivo)
+    pub fn supports_alpha(self) -> bool {
+        self != OutputFormat::Jpeg
+    }
+
+    /// Tipo MIME do formato (ex: cabeçalho Content-Type do modo serve)
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            OutputFormat::Jpeg => "image/jpeg",
+            OutputFormat::Png => "image/png",
+            OutputFormat::Webp | OutputFormat::WebpLossy => "image/webp",
+            OutputFormat::Svg => "image/svg+xml",
+        }
+    }
+}
+
+/// Nível de compressão do PNG
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum PngCompression {
+    Fast,
+    #[default]
+    Default,
+    Best,
+}
+
+/// Opções do codificador de saída
+#[derive(Debug, Clone)]
+pub struct EncoderOptions {
+    /// Formato explícito; quando `None`, é deduzido da extensão do arquivo
+    pub format: Option<OutputFormat>,
+    /// Qualidade (1-100) para JPEG e WebP com perdas
+    pub quality: u8,
+    pub png_compression: PngCompression,
+    /// Gera JPEG progressivo
+    pub progressive: bool,
+}
+
+impl Default for EncoderOptions {
+    fn default() -> Self {
+        Self {
+            format: None,
+            quality: DEFAULT_OUTPUT_QUALITY,
+            png_compression: PngCompression::default(),
+            progressive: false,
+        }
+    }
+}
+
+/// Codifica a imagem para o caminho de saída: usa `--format` se informado,
+/// senão a extensão; extensões não gerenciadas (tiff, bmp...) usam o
+/// codificador padrão da crate `image`.
+pub fn encode_for_path(image: &RgbaImage, path: &Path, options: &EncoderOptions) -> AppResult<Vec<u8>> {
+    if let Some(format) = output_format_for(path, options) {
+        return encode(image, format, options);
+    }
+
+    let format = ImageFormat::from_path(path)?;
+    let mut encoded = Cursor::new(Vec::new());
+    image.write_to(&mut encoded, format)?;
+    Ok(encoded.into_inner())
+}
+
+/// Formato gerenciado que será usado para o caminho (`--format` ou extensão)
+pub fn output_format_for(path: &Path, options: &EncoderOptions) -> Option<OutputFormat> {
+    options.format.or_else(|| OutputFormat::from_path(path))
+}
+
+/// Codifica a imagem no formato indicado
+pub fn encode(image: &RgbaImage, format: OutputFormat, options: &EncoderOptions) -> AppResult<Vec<u8>> {
+    let (width, height) = image.dimensions();
+    debug!("🧩 Codificando {:?} (qualidade {}, progressivo: {}, PNG: {:?})",
+             format, options.quality, options.progressive, options.png_compression);
+
+    match format {
+        OutputFormat::Jpeg => {
+            let mut output = Vec::new();
+            let mut encoder = jpeg_encoder::Encoder::new(&mut output, options.quality);
+            encoder.set_progressive(options.progressive);
+            // JPEG não tem canal alfa: o encoder ignora o quarto byte
+            encoder
+                .encode(image.as_raw(), to_u16(width)?, to_u16(height)?, jpeg_encoder::ColorType::Rgba)
+                .map_err(|e| AppError::InvalidFormat(format!("Falha ao codificar JPEG: {}", e)))?;
+            Ok(output)
+        }
+        OutputFormat::Png => {
+            let compression = match options.png_compression {
+                PngCompression::Fast => CompressionType::Fast,
+                PngCompression::Default => CompressionType::Default,
+                PngCompression::Best => CompressionType::Best,
+            };
+            let mut output = Vec::new();
+            PngEncoder::new_with_quality(&mut output, compression, FilterType::Adaptive)
+                .write_image(image.as_raw(), width, height, image::ColorType::Rgba8)?;
+            Ok(output)
+        }
+        OutputFormat::Webp | OutputFormat::WebpLossy => {
+            check_webp_dimensions(width, height)?;
+            let lossless = format == OutputFormat::Webp;
+            // `encode`/`encode_lossless` fazem unwrap internamente; `encode_simple` devolve o erro
+            let encoded = webp::Encoder::from_rgba(image.as_raw(), width, height)
+                .encode_simple(lossless, if lossless { 75.0 } else { options.quality as f32 })
+                .map_err(|e| AppError::InvalidFormat(format!("Falha ao codificar WebP: {:?}", e)))?;
+            Ok(encoded.to_vec())
+        }
+        OutputFormat::Svg => Err(AppError::InvalidFormat(
+            "SVG é gerado pelo renderizador vetorial, não pelo codificador de imagens".to_string()
+        )),
+    }
+}
+
+/// WebP limita as dimensões a `WEBP_MAX_DIMENSION` pixels por lado
+pub fn check_webp_dimensions(width: u32, height: u32) -> AppResult<()> {
+    if width > WEBP_MAX_DIMENSION || height > WEBP_MAX_DIMENSION {
+        return Err(AppError::InvalidFormat(format!(
+            "Imagem {}x{} excede o limite do WebP ({} pixels por lado); use --format png ou jpeg",
+            width, height, WEBP_MAX_DIMENSION
+        )));
+    }
+    Ok(())
+}
+
+/// JPEG limita as dimensões a 65535 pixels
+fn to_u16(value: u32) -> AppResult<u16> {
+    u16::try_from(value)
+        .map_err(|_| AppError::InvalidFormat(format!("Dimensão {} excede o limite do JPEG (65535)", value)))
+}
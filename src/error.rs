@@ -2,32 +2,166 @@
 // src/error.rs - Gerenciamento centralizado de erros
 // ============================================================================
 
+use std::error::Error;
 use std::fmt;
+use std::path::{Path, PathBuf};
 
 pub type AppResult<T> = Result<T, AppError>;
 
+/// Erros da aplicação. A mensagem (`Display`) já inclui a causa imediata;
+/// `source()` expõe essa causa para quem quiser percorrer a cadeia.
 #[derive(Debug)]
 pub enum AppError {
     IoError(std::io::Error),
+    /// Falha de E/S ao acessar um arquivo conhecido
+    FileError { path: PathBuf, source: std::io::Error },
     ImageError(image::ImageError),
     ParseError(String),
+    /// TCX ou GPX com XML malformado ou fora do esquema, com arquivo e posição quando conhecidos
+    XmlError {
+        file: Option<PathBuf>,
+        line: Option<usize>,
+        column: Option<usize>,
+        source: quick_xml::DeError,
+    },
+    /// Arquivo FIT que não pôde ser decodificado
+    FitError(fitparser::Error),
+    /// CRC do arquivo FIT não confere (arquivo truncado ou corrompido)
+    CorruptFit { expected: u16, calculated: u16 },
+    /// Campo obrigatório ausente no arquivo de atividade
+    MissingField { format: &'static str, field: &'static str },
+    /// Esporte fora dos valores aceitos pelo formato (ex: `Sport` do TCX)
+    UnsupportedSport { format: &'static str, sport: String },
     FontError(String),
+    /// Recurso obrigatório (ex: fonte) não encontrado no disco
+    MissingAsset { kind: &'static str, path: PathBuf },
+    /// Estatísticas e marca d'água não cabem na imagem
+    LayoutDoesNotFit { needed_width: u32, needed_height: u32, width: u32, height: u32 },
     InvalidFormat(String),
+    /// O arquivo de saída já existe e a política de colisão não permite substituí-lo
+    OutputExists(PathBuf),
+}
+
+impl AppError {
+    /// Erro de E/S com o caminho do arquivo envolvido
+    pub fn file(path: &Path) -> impl FnOnce(std::io::Error) -> AppError + '_ {
+        move |source| AppError::FileError { path: path.to_path_buf(), source }
+    }
+
+    /// Associa o arquivo de origem a erros de parsing que ainda não o têm
+    pub fn with_file(self, path: &Path) -> AppError {
+        match self {
+            AppError::XmlError { file: None, line, column, source } => {
+                AppError::XmlError { file: Some(path.to_path_buf()), line, column, source }
+            }
+            other => other,
+        }
+    }
+
+    /// Identificador estável do tipo de erro (logs, respostas JSON do servidor)
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::IoError(_) => "io",
+            AppError::FileError { .. } => "file",
+            AppError::ImageError(_) => "image",
+            AppError::ParseError(_) => "parse",
+            AppError::XmlError { .. } => "xml",
+            AppError::FitError(_) => "fit",
+            AppError::CorruptFit { .. } => "corrupt-fit",
+            AppError::MissingField { .. } => "missing-field",
+            AppError::UnsupportedSport { .. } => "unsupported-sport",
+            AppError::FontError(_) => "font",
+            AppError::MissingAsset { .. } => "missing-asset",
+            AppError::LayoutDoesNotFit { .. } => "layout-does-not-fit",
+            AppError::InvalidFormat(_) => "invalid-input",
+            AppError::OutputExists(_) => "output-exists",
+        }
+    }
+
+    /// Código de saída do processo, para scripts:
+    /// 1 E/S, 2 opções inválidas, 3 atividade inválida, 4 imagem inválida,
+    /// 5 fonte/recurso ausente, 6 overlay não cabe, 7 saída já existe
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            AppError::IoError(_) | AppError::FileError { .. } => 1,
+            AppError::InvalidFormat(_) => 2,
+            AppError::ParseError(_)
+            | AppError::XmlError { .. }
+            | AppError::FitError(_)
+            | AppError::CorruptFit { .. }
+            | AppError::MissingField { .. }
+            | AppError::UnsupportedSport { .. } => 3,
+            AppError::ImageError(_) => 4,
+            AppError::FontError(_) | AppError::MissingAsset { .. } => 5,
+            AppError::LayoutDoesNotFit { .. } => 6,
+            AppError::OutputExists(_) => 7,
+        }
+    }
 }
 
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             AppError::IoError(e) => write!(f, "Erro de E/S: {}", e),
+            AppError::FileError { path, source } if source.kind() == std::io::ErrorKind::NotFound => {
+                write!(f, "Arquivo não encontrado: {:?}", path)
+            }
+            AppError::FileError { path, source } => write!(f, "Erro ao acessar {:?}: {}", path, source),
             AppError::ImageError(e) => write!(f, "Erro de imagem: {}", e),
             AppError::ParseError(e) => write!(f, "Erro de parsing: {}", e),
+            AppError::XmlError { file, line, column, source } => {
+                write!(f, "XML inválido")?;
+                if let Some(file) = file {
+                    write!(f, " em {:?}", file)?;
+                }
+                match (line, column) {
+                    (Some(line), Some(column)) => write!(f, " (linha {}, coluna {})", line, column)?,
+                    (Some(line), None) => write!(f, " (linha {})", line)?,
+                    _ => {}
+                }
+                write!(f, ": {}", source)
+            }
+            AppError::FitError(e) => write!(f, "Arquivo FIT inválido: {}", e),
+            AppError::CorruptFit { expected, calculated } => write!(
+                f,
+                "Arquivo FIT corrompido: CRC esperado {:#06x}, calculado {:#06x} (arquivo truncado?)",
+                expected, calculated
+            ),
+            AppError::MissingField { format, field } => {
+                write!(f, "Campo obrigatório '{}' ausente no arquivo {}", field, format)
+            }
+            AppError::UnsupportedSport { format, sport } => {
+                write!(f, "Esporte '{}' não suportado no arquivo {}", sport, format)
+            }
             AppError::FontError(e) => write!(f, "Erro de fonte: {}", e),
+            AppError::MissingAsset { kind, path } => write!(f, "{} não encontrada em {:?}", kind, path),
+            AppError::LayoutDoesNotFit { needed_width, needed_height, width, height } => write!(
+                f,
+                "O overlay ({}x{}) não cabe na imagem ({}x{}) nem com a fonte mínima; use uma imagem ou tela maior",
+                needed_width, needed_height, width, height
+            ),
             AppError::InvalidFormat(e) => write!(f, "Formato inválido: {}", e),
+            AppError::OutputExists(path) => write!(
+                f,
+                "Arquivo de saída já existe: {:?} (use --force para substituir ou --on-conflict suffix)",
+                path
+            ),
         }
     }
 }
 
-impl std::error::Error for AppError {}
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AppError::IoError(e) => Some(e),
+            AppError::FileError { source, .. } => Some(source),
+            AppError::ImageError(e) => Some(e),
+            AppError::XmlError { source, .. } => Some(source),
+            AppError::FitError(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl From<std::io::Error> for AppError {
     fn from(error: std::io::Error) -> Self {
@@ -41,8 +175,19 @@ impl From<image::ImageError> for AppError {
     }
 }
 
+impl From<fitparser::Error> for AppError {
+    fn from(error: fitparser::Error) -> Self {
+        match *error {
+            fitparser::ErrorKind::InvalidCrc((_, _, expected, calculated)) => {
+                AppError::CorruptFit { expected, calculated }
+            }
+            _ => AppError::FitError(error),
+        }
+    }
+}
+
 impl From<Box<dyn std::error::Error>> for AppError {
     fn from(error: Box<dyn std::error::Error>) -> Self {
         AppError::ParseError(error.to_string())
     }
-}
\ No newline at end of file
+}
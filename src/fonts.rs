@@ -0,0 +1,559 @@
+// ============================================================================
+// src/fonts.rs - Escolha das fontes do overlay (arquivo ou família instalada),
+// pesos e fallback por glifo
+// ============================================================================
+
+use clap::ValueEnum;
+use imageproc::drawing::text_size;
+use log::{debug, info, warn};
+use rusttype::{point, Scale};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::assets::{self, Asset, FontAsset};
+use crate::constants::FALLBACK_FONT_FAMILIES;
+use crate::error::{AppError, AppResult};
+use crate::renderer::FontRole;
+
+/// Profundidade máxima ao percorrer os diretórios de fontes do sistema
+const MAX_SCAN_DEPTH: usize = 8;
+/// Extensões reconhecidas como fontes
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc", "otc"];
+/// Tamanho de cada registro do diretório de tabelas (tag, checksum, deslocamento, tamanho)
+const TABLE_RECORD_SIZE: usize = 16;
+/// Limites contra arquivos corrompidos ao indexar as fontes do sistema
+const MAX_COLLECTION_FACES: u32 = 1024;
+const MAX_INDEX_TABLE_BYTES: u32 = 4 * 1024 * 1024;
+
+/// Fonte escolhida pelo usuário: arquivo ou nome de família instalada
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FontSpec {
+    /// Arquivo .ttf/.otf/.ttc (valores com separador de diretório ou extensão de fonte)
+    Path(PathBuf),
+    /// Família procurada nas fontes do sistema (ex: "Noto Sans")
+    Family(String),
+}
+
+/// Peso da fonte de texto
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum FontWeight {
+    #[default]
+    Regular,
+    Bold,
+}
+
+/// Fontes de rótulos e números; sem fonte de números, ela usa a dos rótulos
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FontOptions {
+    pub label: Option<FontSpec>,
+    pub label_weight: FontWeight,
+    pub number: Option<FontSpec>,
+    pub number_weight: FontWeight,
+}
+
+/// Fonte principal seguida das fontes usadas para os caracteres que ela não tem
+#[derive(Clone)]
+pub struct FontStack {
+    fonts: Vec<FontAsset>,
+}
+
+/// Trecho de texto desenhado com uma única fonte da pilha, com o
+/// deslocamento em relação à posição do texto
+pub struct TextRun<'a> {
+    pub font: &'a FontAsset,
+    pub text: &'a str,
+    pub x: i32,
+    /// Alinha a linha de base com a da fonte principal
+    pub y: i32,
+}
+
+/// Fontes do overlay por papel: rótulos, números e ícones
+#[derive(Clone)]
+pub struct FontSet {
+    pub label: FontStack,
+    pub number: FontStack,
+    pub icon: FontStack,
+    /// Fonte do conjunto de ícones, quando ele tem uma
+    pub icon_set: Option<FontStack>,
+}
+
+/// Fonte encontrada nos diretórios do sistema
+#[derive(Debug, Clone)]
+pub struct SystemFont {
+    pub path: PathBuf,
+    /// Índice da fonte em coleções (.ttc)
+    pub index: u32,
+    /// Nomes da família (tipográfica e legada, ex: "DejaVu Sans" e "DejaVu Sans Light")
+    pub families: Vec<String>,
+    pub weight: u16,
+    pub italic: bool,
+    /// Largura do OS/2 (5 = normal)
+    pub width: u16,
+    /// Intervalos de caracteres (inclusivos) mapeados pelo `cmap`, em ordem
+    pub coverage: Vec<(u32, u32)>,
+}
+
+impl SystemFont {
+    /// Indica se o `cmap` da face mapeia o caractere, sem abrir o arquivo
+    pub fn covers(&self, c: char) -> bool {
+        let c = c as u32;
+        let next = self.coverage.partition_point(|&(_, end)| end < c);
+        self.coverage.get(next).is_some_and(|&(start, _)| start <= c)
+    }
+}
+
+impl fmt::Display for FontSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontSpec::Path(path) => write!(f, "{}", path.display()),
+            FontSpec::Family(family) => f.write_str(family),
+        }
+    }
+}
+
+impl FromStr for FontSpec {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err("Fonte vazia (use um arquivo .ttf/.otf ou o nome de uma família)".to_string());
+        }
+        let has_font_extension = Path::new(value)
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| FONT_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+        if !has_font_extension && !value.contains(['/', '\\']) {
+            return Ok(FontSpec::Family(value.to_string()));
+        }
+        match (value.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => Ok(FontSpec::Path(home.join(rest))),
+            _ => Ok(FontSpec::Path(PathBuf::from(value))),
+        }
+    }
+}
+
+impl FontWeight {
+    /// Peso CSS correspondente
+    pub fn css_weight(self) -> u16 {
+        match self {
+            FontWeight::Regular => 400,
+            FontWeight::Bold => 700,
+        }
+    }
+
+    fn default_asset(self) -> Asset {
+        match self {
+            FontWeight::Regular => Asset::TextFont,
+            FontWeight::Bold => Asset::BoldFont,
+        }
+    }
+}
+
+impl FontOptions {
+    /// Carrega as fontes de rótulos, números e ícones
+    pub fn load(&self) -> AppResult<FontSet> {
+        let label = load(self.label.as_ref(), self.label_weight)?;
+        let number = match (&self.number, &self.label) {
+            (None, _) if self.number_weight == self.label_weight => label.clone(),
+            (Some(spec), _) | (None, Some(spec)) => load(Some(spec), self.number_weight)?,
+            (None, None) => load(None, self.number_weight)?,
+        };
+        debug!("Fontes: rótulos {} ({}), números {} ({})", label.family, label.source, number.family, number.source);
+        Ok(FontSet {
+            label: FontStack::new(label),
+            number: FontStack::new(number),
+            icon: FontStack::new(assets::load_font(Asset::IconFont)?),
+            icon_set: None,
+        })
+    }
+}
+
+/// Carrega a fonte indicada (ou a padrão) no peso pedido. Com um arquivo,
+/// vale o peso do próprio arquivo.
+pub fn load(spec: Option<&FontSpec>, weight: FontWeight) -> AppResult<FontAsset> {
+    match spec {
+        None => assets::load_font(weight.default_asset()),
+        Some(FontSpec::Path(path)) => {
+            let font = FontAsset::from_file(path, 0)?;
+            info!("🔤 Fonte: {} ({})", font.family, path.display());
+            Ok(font)
+        }
+        Some(FontSpec::Family(family)) => {
+            match assets::load_font(weight.default_asset()) {
+                Ok(bundled) if bundled.family.eq_ignore_ascii_case(family) => return Ok(bundled),
+                _ => {}
+            }
+            let system_font = find_family(family, weight)?;
+            let font = FontAsset::from_file(&system_font.path, system_font.index)?;
+            info!("🔤 Fonte: {} {} ({})", font.family, font.weight, system_font.path.display());
+            Ok(font)
+        }
+    }
+}
+
+/// Procura a família nas fontes do sistema, escolhendo a variante mais
+/// próxima do peso pedido (não itálica e de largura normal)
+pub fn find_family(family: &str, weight: FontWeight) -> AppResult<&'static SystemFont> {
+    let target = weight.css_weight();
+    let best = system_fonts()
+        .iter()
+        .filter(|font| font.families.iter().any(|name| name.eq_ignore_ascii_case(family)))
+        .min_by_key(|font| (font.italic, font.width.abs_diff(5), font.weight.abs_diff(target), font.path.clone()));
+
+    let Some(best) = best else {
+        let query = family.to_lowercase();
+        let mut similar: Vec<&str> = system_fonts()
+            .iter()
+            .flat_map(|font| font.families.iter().map(String::as_str))
+            .filter(|name| name.to_lowercase().contains(&query))
+            .collect();
+        similar.sort_unstable();
+        similar.dedup();
+        similar.truncate(5);
+        let hint = if similar.is_empty() { String::new() } else { format!(" Semelhantes: {}.", similar.join(", ")) };
+        return Err(AppError::FontError(format!(
+            "Família '{}' não encontrada entre as {} fontes do sistema (diretórios: {}).{}",
+            family,
+            system_fonts().len(),
+            font_dirs().iter().map(|dir| dir.display().to_string()).collect::<Vec<_>>().join(", "),
+            hint
+        )));
+    };
+    if best.weight.abs_diff(target) >= 200 {
+        warn!("⚠️  Família '{}' sem variante de peso {}; usando peso {}", family, target, best.weight);
+    }
+    Ok(best)
+}
+
+/// Fontes instaladas, lidas uma única vez por processo
+pub fn system_fonts() -> &'static [SystemFont] {
+    static FONTS: OnceLock<Vec<SystemFont>> = OnceLock::new();
+    FONTS.get_or_init(|| {
+        let mut fonts = Vec::new();
+        for dir in font_dirs() {
+            scan_dir(&dir, 0, &mut fonts);
+        }
+        debug!("🔤 {} fontes encontradas no sistema", fonts.len());
+        fonts
+    })
+}
+
+/// Diretórios de fontes do usuário e do sistema (Linux, macOS e Windows),
+/// além de `fonts/` nos diretórios de recursos
+pub fn font_dirs() -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = assets::search_dirs().into_iter().map(|dir| dir.join("fonts")).collect();
+    candidates.extend(dirs::font_dir());
+    candidates.extend(dirs::home_dir().map(|home| home.join(".fonts")));
+    candidates.extend(
+        ["/usr/share/fonts", "/usr/local/share/fonts", "/Library/Fonts", "/System/Library/Fonts"].map(PathBuf::from),
+    );
+    candidates.extend(std::env::var_os("WINDIR").map(|dir| PathBuf::from(dir).join("Fonts")));
+
+    let mut dirs = Vec::new();
+    for dir in candidates {
+        if dir.is_dir() && !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+fn scan_dir(dir: &Path, depth: usize, fonts: &mut Vec<SystemFont>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth < MAX_SCAN_DEPTH {
+                scan_dir(&path, depth + 1, fonts);
+            }
+            continue;
+        }
+        let is_font = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| FONT_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+        if is_font {
+            read_font_file(&path, fonts);
+        }
+    }
+}
+
+/// Indexa as faces de um arquivo de fonte lendo só o cabeçalho, o diretório
+/// de tabelas e as tabelas `name`, `OS/2` e `cmap` de cada face, sem carregar
+/// os contornos dos glifos (arquivos CJK passam de dezenas de MB)
+fn read_font_file(path: &Path, fonts: &mut Vec<SystemFont>) {
+    let Ok(mut file) = File::open(path) else {
+        return;
+    };
+    let Ok(header) = read_at(&mut file, 0, 12) else {
+        return;
+    };
+    // Coleções (.ttc/.otc): "ttcf", versão, número de faces e o deslocamento de cada uma
+    let face_offsets: Vec<u32> = if header.starts_with(b"ttcf") {
+        let count = be_u32(&header, 8).min(MAX_COLLECTION_FACES) as usize;
+        let Ok(offsets) = read_at(&mut file, 12, count * 4) else {
+            return;
+        };
+        offsets.chunks_exact(4).map(|offset| be_u32(offset, 0)).collect()
+    } else {
+        vec![0]
+    };
+
+    for (index, offset) in face_offsets.into_iter().enumerate() {
+        if let Some(font) = read_face(&mut file, path, index as u32, offset) {
+            fonts.push(font);
+        }
+    }
+}
+
+/// Lê uma face a partir do seu diretório de tabelas (em `offset` no arquivo)
+fn read_face(file: &mut File, path: &Path, index: u32, offset: u32) -> Option<SystemFont> {
+    let header = read_at(file, offset as u64, 12).ok()?;
+    let num_tables = u16::from_be_bytes([header[4], header[5]]) as usize;
+    let records = read_at(file, offset as u64 + 12, num_tables * TABLE_RECORD_SIZE).ok()?;
+    let record = |tag: &[u8; 4]| records.chunks_exact(TABLE_RECORD_SIZE).find(|record| &record[..4] == tag);
+    // Sem `head` e `cmap` a fonte não pode ser usada para desenhar texto
+    if record(b"head").is_none() || record(b"cmap").is_none() {
+        return None;
+    }
+    let mut table = |tag: &[u8; 4]| {
+        let record = record(tag)?;
+        let length = be_u32(record, 12).min(MAX_INDEX_TABLE_BYTES);
+        read_at(file, be_u32(record, 8) as u64, length as usize).ok()
+    };
+
+    let name_data = table(b"name")?;
+    let families = face_families(ttf_parser::name::Table::parse(&name_data)?.names);
+    if families.is_empty() {
+        return None;
+    }
+    let os2_data = table(b"OS/2");
+    let os2 = os2_data.as_deref().and_then(ttf_parser::os2::Table::parse);
+    let coverage = cmap_coverage(&table(b"cmap")?);
+    Some(SystemFont {
+        path: path.to_path_buf(),
+        index,
+        families,
+        weight: os2.map_or(ttf_parser::Weight::Normal, |os2| os2.weight()).to_number(),
+        italic: os2.is_some_and(|os2| os2.style() != ttf_parser::Style::Normal),
+        width: os2.map_or(ttf_parser::Width::Normal, |os2| os2.width()).to_number(),
+        coverage,
+    })
+}
+
+/// Caracteres das subtabelas Unicode do `cmap`, agrupados em intervalos contínuos
+fn cmap_coverage(data: &[u8]) -> Vec<(u32, u32)> {
+    let mut codepoints = Vec::new();
+    if let Some(cmap) = ttf_parser::cmap::Table::parse(data) {
+        for subtable in cmap.subtables.into_iter().filter(|subtable| subtable.is_unicode()) {
+            subtable.codepoints(|c| codepoints.push(c));
+        }
+    }
+    codepoints.sort_unstable();
+    codepoints.dedup();
+
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for c in codepoints {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == c => *end = c,
+            _ => ranges.push((c, c)),
+        }
+    }
+    ranges
+}
+
+/// Lê `length` bytes a partir de `offset`
+fn read_at(file: &mut File, offset: u64, length: usize) -> io::Result<Vec<u8>> {
+    let mut buffer = vec![0; length];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn be_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+/// Nomes de família da fonte (tabela `name`): o tipográfico (que agrupa os pesos) primeiro
+pub fn face_families(names: ttf_parser::name::Names) -> Vec<String> {
+    let mut families = Vec::new();
+    for name_id in [ttf_parser::name_id::TYPOGRAPHIC_FAMILY, ttf_parser::name_id::FAMILY] {
+        for name in names.into_iter().filter(|name| name.name_id == name_id) {
+            let value = name.to_string().or_else(|| {
+                // Nomes da plataforma Macintosh (Roman): aceitos quando ASCII
+                name.name.is_ascii().then(|| String::from_utf8_lossy(name.name).into_owned())
+            });
+            if let Some(value) = value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()) {
+                if !families.contains(&value) {
+                    families.push(value);
+                }
+            }
+        }
+    }
+    families
+}
+
+impl FontStack {
+    pub fn new(primary: FontAsset) -> Self {
+        Self { fonts: vec![primary] }
+    }
+
+    /// Fonte principal (métricas, linha de base e tamanho no SVG)
+    pub fn primary(&self) -> &FontAsset {
+        &self.fonts[0]
+    }
+
+    /// Todas as fontes da pilha, a principal primeiro
+    pub fn fonts(&self) -> &[FontAsset] {
+        &self.fonts
+    }
+
+    /// Descrição das fontes para o `--explain`, ex: "DejaVu Sans 400 (embutido no binário)"
+    pub fn describe(&self) -> Vec<String> {
+        self.fonts.iter().map(|font| format!("{} {} ({})", font.family, font.weight, font.source)).collect()
+    }
+
+    /// Acrescenta fontes substitutas para os caracteres de `text` que nenhuma
+    /// fonte da pilha tem: primeiro a fonte embutida, depois as famílias de
+    /// `FALLBACK_FONT_FAMILIES` e por fim qualquer fonte instalada. Das fontes
+    /// instaladas só são abertas as que o índice diz ter algum caractere em falta.
+    pub fn cover(&mut self, text: &str) {
+        let mut missing: Vec<char> = text.chars().filter(|&c| !self.fonts.iter().any(|font| font.has_glyph(c))).collect();
+        missing.sort_unstable();
+        missing.dedup();
+        if missing.is_empty() {
+            return;
+        }
+
+        if let Ok(bundled) = assets::load_font(Asset::TextFont) {
+            self.try_fallback(bundled, &mut missing);
+        }
+        let mut candidates: Vec<&SystemFont> = system_fonts().iter().filter(|font| !font.italic).collect();
+        candidates.sort_by_key(|font| {
+            let rank = FALLBACK_FONT_FAMILIES
+                .iter()
+                .position(|family| font.families.iter().any(|name| name.eq_ignore_ascii_case(family)))
+                .unwrap_or(FALLBACK_FONT_FAMILIES.len());
+            (rank, font.weight.abs_diff(self.primary().weight), font.path.clone())
+        });
+        for candidate in candidates {
+            if missing.is_empty() {
+                break;
+            }
+            if !missing.iter().any(|&c| candidate.covers(c)) {
+                continue;
+            }
+            if let Ok(font) = FontAsset::from_file(&candidate.path, candidate.index) {
+                self.try_fallback(font, &mut missing);
+            }
+        }
+
+        for c in missing {
+            warn!("⚠️  Nenhuma fonte disponível tem o caractere '{}' (U+{:04X})", c, c as u32);
+        }
+    }
+
+    /// Usa a fonte como substituta se ela tiver algum dos caracteres em falta
+    fn try_fallback(&mut self, font: FontAsset, missing: &mut Vec<char>) {
+        let covered: String = missing.iter().filter(|&&c| font.has_glyph(c)).collect();
+        if covered.is_empty() {
+            return;
+        }
+        info!("🔤 Fonte substituta para \"{}\": {} ({})", covered, font.family, font.source);
+        missing.retain(|&c| !font.has_glyph(c));
+        self.fonts.push(font);
+    }
+
+    /// Divide o texto em trechos com uma única fonte cada. Espaços seguem a
+    /// fonte do trecho atual; caracteres sem glifo em nenhuma fonte usam a principal.
+    pub fn runs<'a>(&'a self, text: &'a str) -> Vec<(&'a FontAsset, &'a str)> {
+        let mut runs: Vec<(usize, usize, usize)> = Vec::new();
+        for (offset, c) in text.char_indices() {
+            let end = offset + c.len_utf8();
+            let current = runs.last().map(|&(index, _, _)| index);
+            let index = match current {
+                Some(index) if self.fonts[index].has_glyph(c) => index,
+                _ => self.fonts.iter().position(|font| font.has_glyph(c)).unwrap_or(0),
+            };
+            match runs.last_mut() {
+                Some((last, _, run_end)) if *last == index => *run_end = end,
+                _ => runs.push((index, offset, end)),
+            }
+        }
+        runs.into_iter().map(|(index, start, end)| (&self.fonts[index], &text[start..end])).collect()
+    }
+
+    /// Posiciona os trechos do texto relativos ao ponto de desenho
+    pub fn layout<'a>(&'a self, scale: Scale, text: &'a str) -> Vec<TextRun<'a>> {
+        let ascent = self.primary().v_metrics(scale).ascent;
+        let mut x = 0;
+        self.runs(text)
+            .into_iter()
+            .map(|(font, text)| {
+                let run = TextRun { font, text, x, y: (ascent - font.v_metrics(scale).ascent).round() as i32 };
+                x += advance(font, scale, text);
+                run
+            })
+            .collect()
+    }
+
+    /// Avanço horizontal do texto (posição onde o próximo texto começa)
+    pub fn advance(&self, scale: Scale, text: &str) -> i32 {
+        self.runs(text).into_iter().map(|(font, text)| advance(font, scale, text)).sum()
+    }
+
+    /// Largura desenhada do texto, como `imageproc::drawing::text_size`
+    pub fn width(&self, scale: Scale, text: &str) -> i32 {
+        let runs = self.layout(scale, text);
+        match runs.last() {
+            Some(last) => last.x + text_size(scale, last.font, last.text).0,
+            None => 0,
+        }
+    }
+}
+
+/// Avanço do texto em uma única fonte, com kerning
+fn advance(font: &FontAsset, scale: Scale, text: &str) -> i32 {
+    font.layout(text, scale, point(0.0, 0.0))
+        .last()
+        .map(|glyph| glyph.position().x + glyph.unpositioned().h_metrics().advance_width)
+        .unwrap_or(0.0)
+        .round() as i32
+}
+
+impl FontSet {
+    /// Pilha de fontes usada por um papel
+    pub fn stack(&self, role: FontRole) -> &FontStack {
+        match role {
+            FontRole::Label => &self.label,
+            FontRole::Number => &self.number,
+            FontRole::Icon => &self.icon,
+            FontRole::IconSet => self.icon_set.as_ref().unwrap_or(&self.icon),
+        }
+    }
+
+    /// Garante fallback para os caracteres dos rótulos e dos números
+    pub fn cover(&mut self, labels: &str, numbers: &str) {
+        self.label.cover(labels);
+        self.number.cover(numbers);
+    }
+
+    /// Fontes distintas (família e peso) de todas as pilhas, para o `<style>` do SVG
+    pub fn unique_fonts(&self) -> Vec<&FontAsset> {
+        let mut unique: Vec<&FontAsset> = Vec::new();
+        let stacks = [Some(&self.label), Some(&self.number), Some(&self.icon), self.icon_set.as_ref()];
+        for font in stacks.into_iter().flatten().flat_map(|stack| stack.fonts.iter()) {
+            if !unique.iter().any(|other| other.family == font.family && other.weight == font.weight) {
+                unique.push(font);
+            }
+        }
+        unique
+    }
+}
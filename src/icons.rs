@@ -0,0 +1,351 @@
+// ============================================================================
+// src/icons.rs - Ícones do overlay e conjuntos de ícones (fonte de ícones,
+// arquivos SVG/PNG pintados na cor da estatística)
+// ============================================================================
+
+use image::{imageops, Rgba, RgbaImage};
+use log::{debug, info, warn};
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::assets::FontAsset;
+use crate::constants::*;
+use crate::error::{AppError, AppResult};
+use crate::fonts::{self, FontSpec, FontWeight};
+use crate::renderer::FontRole;
+
+/// Ícones que um conjunto pode definir
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Icon {
+    Time,
+    Moving,
+    Pace,
+    Calories,
+    HeartRate,
+    Calendar,
+    Device,
+    Camera,
+    Distance,
+    Elevation,
+    Power,
+    Cadence,
+    Sport(SportKind),
+}
+
+/// Tipo de esporte, para os ícones de esporte
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SportKind {
+    Running,
+    Cycling,
+    Swimming,
+    Walking,
+    Hiking,
+    Other,
+}
+
+/// Conjunto de ícones: imagens e glifos de uma fonte própria, com os
+/// ícones do FontAwesome embutido para o que o conjunto não define
+#[derive(Clone)]
+pub struct IconSet {
+    /// Diretório do conjunto (`None` no conjunto padrão)
+    dir: Option<PathBuf>,
+    font: Option<FontAsset>,
+    glyphs: HashMap<Icon, String>,
+    images: HashMap<Icon, IconImage>,
+    /// Pinta as imagens com a cor da estatística (só o canal alfa é mantido)
+    tint: bool,
+}
+
+/// Imagem de um ícone, rasterizada no tamanho do texto ao desenhar
+#[derive(Clone)]
+enum IconImage {
+    Svg(Box<usvg::Tree>),
+    Raster(RgbaImage),
+}
+
+/// `icons.toml` do conjunto
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IconManifest {
+    /// Fonte de ícones: arquivo (relativo ao diretório do conjunto) ou família instalada
+    font: Option<String>,
+    /// Caractere de cada ícone na fonte, como "U+E8B5" ou o próprio caractere
+    #[serde(default)]
+    glyphs: BTreeMap<String, String>,
+    tint: Option<bool>,
+}
+
+impl Icon {
+    /// Todos os ícones, na ordem da documentação
+    pub const ALL: [Icon; 18] = [
+        Icon::Time,
+        Icon::Moving,
+        Icon::Pace,
+        Icon::Calories,
+        Icon::HeartRate,
+        Icon::Calendar,
+        Icon::Device,
+        Icon::Camera,
+        Icon::Distance,
+        Icon::Elevation,
+        Icon::Power,
+        Icon::Cadence,
+        Icon::Sport(SportKind::Running),
+        Icon::Sport(SportKind::Cycling),
+        Icon::Sport(SportKind::Swimming),
+        Icon::Sport(SportKind::Walking),
+        Icon::Sport(SportKind::Hiking),
+        Icon::Sport(SportKind::Other),
+    ];
+
+    /// Nome do ícone nos arquivos do conjunto (`<nome>.svg`) e no `icons.toml`
+    pub fn name(self) -> &'static str {
+        match self {
+            Icon::Time => "time",
+            Icon::Moving => "moving",
+            Icon::Pace => "pace",
+            Icon::Calories => "calories",
+            Icon::HeartRate => "heart-rate",
+            Icon::Calendar => "calendar",
+            Icon::Device => "device",
+            Icon::Camera => "camera",
+            Icon::Distance => "distance",
+            Icon::Elevation => "elevation",
+            Icon::Power => "power",
+            Icon::Cadence => "cadence",
+            Icon::Sport(SportKind::Running) => "sport-running",
+            Icon::Sport(SportKind::Cycling) => "sport-cycling",
+            Icon::Sport(SportKind::Swimming) => "sport-swimming",
+            Icon::Sport(SportKind::Walking) => "sport-walking",
+            Icon::Sport(SportKind::Hiking) => "sport-hiking",
+            Icon::Sport(SportKind::Other) => "sport-other",
+        }
+    }
+
+    /// Glifo no FontAwesome embutido
+    pub fn default_glyph(self) -> &'static str {
+        match self {
+            Icon::Time => ICON_TIME,
+            Icon::Moving => ICON_MOVING,
+            Icon::Pace => ICON_PACE,
+            Icon::Calories => ICON_FIRE,
+            Icon::HeartRate => ICON_HEART,
+            Icon::Calendar => ICON_CALENDAR,
+            Icon::Device => ICON_DEVICE,
+            Icon::Camera => ICON_CAMERA,
+            Icon::Distance => ICON_DISTANCE,
+            Icon::Elevation => ICON_ELEVATION,
+            Icon::Power => ICON_POWER,
+            Icon::Cadence => ICON_CADENCE,
+            Icon::Sport(SportKind::Running) => ICON_RUNNING,
+            Icon::Sport(SportKind::Cycling) => ICON_CYCLING,
+            Icon::Sport(SportKind::Swimming) => ICON_SWIMMING,
+            Icon::Sport(SportKind::Walking) => ICON_WALKING,
+            Icon::Sport(SportKind::Hiking) => ICON_HIKING,
+            Icon::Sport(SportKind::Other) => ICON_OTHER_SPORT,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Icon> {
+        Icon::ALL.into_iter().find(|icon| icon.name() == name)
+    }
+}
+
+impl SportKind {
+    /// Tipo pelo nome do esporte no arquivo de atividade (TCX "Running"/"Biking", FIT "cycling"...)
+    pub fn from_sport(sport: Option<&str>) -> SportKind {
+        let sport = sport.unwrap_or("").to_lowercase();
+        if sport.contains("run") {
+            SportKind::Running
+        } else if sport.contains("bik") || sport.contains("cycl") {
+            SportKind::Cycling
+        } else if sport.contains("swim") {
+            SportKind::Swimming
+        } else if sport.contains("walk") {
+            SportKind::Walking
+        } else if sport.contains("hik") {
+            SportKind::Hiking
+        } else {
+            SportKind::Other
+        }
+    }
+}
+
+impl Default for IconSet {
+    fn default() -> Self {
+        Self { dir: None, font: None, glyphs: HashMap::new(), images: HashMap::new(), tint: true }
+    }
+}
+
+impl IconSet {
+    /// Carrega um conjunto de um diretório com `<ícone>.svg`/`<ícone>.png` e
+    /// `icons.toml` opcional, ou diretamente de um `icons.toml`
+    pub fn load(path: &Path) -> AppResult<IconSet> {
+        let metadata = fs::metadata(path).map_err(AppError::file(path))?;
+        let (dir, manifest_path) = if metadata.is_dir() {
+            (path.to_path_buf(), path.join(ICON_SET_MANIFEST))
+        } else {
+            (path.parent().unwrap_or(Path::new("")).to_path_buf(), path.to_path_buf())
+        };
+
+        let manifest = match fs::read_to_string(&manifest_path) {
+            Ok(contents) => toml::from_str::<IconManifest>(&contents).map_err(|e| {
+                AppError::InvalidFormat(format!("Conjunto de ícones inválido em {:?}: {}", manifest_path, e))
+            })?,
+            Err(e) if metadata.is_dir() && e.kind() == std::io::ErrorKind::NotFound => IconManifest::default(),
+            Err(source) => return Err(AppError::FileError { path: manifest_path, source }),
+        };
+
+        let font = match &manifest.font {
+            Some(value) => {
+                let spec = match value.parse::<FontSpec>().map_err(AppError::InvalidFormat)? {
+                    FontSpec::Path(font_path) if font_path.is_relative() => FontSpec::Path(dir.join(font_path)),
+                    spec => spec,
+                };
+                Some(fonts::load(Some(&spec), FontWeight::Regular)?)
+            }
+            None => None,
+        };
+
+        let mut glyphs = HashMap::new();
+        for (name, value) in &manifest.glyphs {
+            let icon = Icon::from_name(name).ok_or_else(|| unknown_icon(name))?;
+            let glyph = parse_glyph(value)
+                .ok_or_else(|| AppError::InvalidFormat(format!("Glifo inválido para '{}': {:?} (use \"U+E8B5\" ou o caractere)", name, value)))?;
+            match &font {
+                Some(font) if glyph.chars().all(|c| font.has_glyph(c)) => {
+                    glyphs.insert(icon, glyph);
+                }
+                Some(font) => {
+                    warn!("⚠️  A fonte de ícones {} não tem o glifo de '{}' ({}); usando o ícone padrão", font.family, name, value)
+                }
+                None => {
+                    return Err(AppError::InvalidFormat(format!("O conjunto define glifos sem uma fonte (`font`) em {:?}", manifest_path)));
+                }
+            }
+        }
+
+        let mut images = HashMap::new();
+        for icon in Icon::ALL {
+            if let Some(image) = load_image(&dir, icon)? {
+                images.insert(icon, image);
+            }
+        }
+
+        let set = IconSet { dir: Some(dir), font, glyphs, images, tint: manifest.tint.unwrap_or(true) };
+        info!("🎨 Conjunto de ícones: {}", set.describe());
+        Ok(set)
+    }
+
+    /// Fonte de ícones própria do conjunto
+    pub fn font(&self) -> Option<&FontAsset> {
+        self.font.as_ref()
+    }
+
+    /// Descrição para logs e `--explain`, ex: "icones/ (3 imagens, 2 glifos de Material Icons)"
+    pub fn describe(&self) -> String {
+        let Some(dir) = &self.dir else {
+            return "FontAwesome (embutido)".to_string();
+        };
+        let mut parts = vec![format!("{} imagens", self.images.len())];
+        if let Some(font) = &self.font {
+            parts.push(format!("{} glifos de {}", self.glyphs.len(), font.family));
+        }
+        format!("{} ({})", dir.display(), parts.join(", "))
+    }
+
+    /// Glifo e fonte de um ícone sem imagem: o da fonte do conjunto ou o do FontAwesome
+    pub fn glyph(&self, icon: Icon) -> (&str, FontRole) {
+        match self.glyphs.get(&icon) {
+            Some(glyph) => (glyph, FontRole::IconSet),
+            None => (icon.default_glyph(), FontRole::Icon),
+        }
+    }
+
+    /// Dimensões da imagem do ícone com altura `size`; `None` quando o ícone é um glifo
+    pub fn image_size(&self, icon: Icon, size: u32) -> Option<(u32, u32)> {
+        let (width, height) = match self.images.get(&icon)? {
+            IconImage::Svg(tree) => (tree.size().width(), tree.size().height()),
+            IconImage::Raster(image) => (image.width() as f32, image.height() as f32),
+        };
+        (size > 0 && height > 0.0).then(|| (((width * size as f32 / height).round() as u32).max(1), size))
+    }
+
+    /// Imagem do ícone com altura `size`, pintada com `color` quando o conjunto
+    /// usa `tint`; `None` quando o ícone é um glifo
+    pub fn render_image(&self, icon: Icon, size: u32, color: Rgba<u8>) -> Option<RgbaImage> {
+        let (width, height) = self.image_size(icon, size)?;
+        let mut image = match self.images.get(&icon)? {
+            IconImage::Svg(tree) => rasterize(tree, width, height)?,
+            IconImage::Raster(image) => imageops::resize(image, width, height, imageops::FilterType::Lanczos3),
+        };
+        if self.tint {
+            for pixel in image.pixels_mut() {
+                let alpha = (pixel[3] as u16 * color[3] as u16 / 255) as u8;
+                *pixel = Rgba([color[0], color[1], color[2], alpha]);
+            }
+        }
+        Some(image)
+    }
+}
+
+/// `<ícone>.svg` ou `<ícone>.png` no diretório do conjunto
+fn load_image(dir: &Path, icon: Icon) -> AppResult<Option<IconImage>> {
+    let svg_path = dir.join(format!("{}.svg", icon.name()));
+    if svg_path.is_file() {
+        let data = fs::read(&svg_path).map_err(AppError::file(&svg_path))?;
+        let tree = usvg::Tree::from_data(&data, &usvg::Options::default())
+            .map_err(|e| AppError::InvalidFormat(format!("Ícone SVG inválido {:?}: {}", svg_path, e)))?;
+        debug!("🎨 Ícone '{}': {:?}", icon.name(), svg_path);
+        return Ok(Some(IconImage::Svg(Box::new(tree))));
+    }
+    let png_path = dir.join(format!("{}.png", icon.name()));
+    if png_path.is_file() {
+        let image = image::open(&png_path)?.to_rgba8();
+        debug!("🎨 Ícone '{}': {:?}", icon.name(), png_path);
+        return Ok(Some(IconImage::Raster(image)));
+    }
+    Ok(None)
+}
+
+/// Rasteriza o SVG no tamanho pedido, convertendo do alfa pré-multiplicado
+fn rasterize(tree: &usvg::Tree, width: u32, height: u32) -> Option<RgbaImage> {
+    let mut pixmap = Pixmap::new(width, height)?;
+    let size = tree.size();
+    let transform = Transform::from_scale(width as f32 / size.width(), height as f32 / size.height());
+    resvg::render(tree, transform, &mut pixmap.as_mut());
+
+    let mut image = RgbaImage::new(width, height);
+    for (pixel, source) in image.pixels_mut().zip(pixmap.pixels()) {
+        let color = source.demultiply();
+        *pixel = Rgba([color.red(), color.green(), color.blue(), color.alpha()]);
+    }
+    Some(image)
+}
+
+/// "U+E8B5" (um ou mais códigos separados por espaço) ou o próprio texto
+fn parse_glyph(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    if !value.to_uppercase().starts_with("U+") {
+        return Some(value.to_string());
+    }
+    value
+        .split_whitespace()
+        .map(|code| {
+            let hex = code.get(2..).filter(|_| code.to_uppercase().starts_with("U+"))?;
+            u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+        })
+        .collect()
+}
+
+fn unknown_icon(name: &str) -> AppError {
+    let names: Vec<&str> = Icon::ALL.iter().map(|icon| icon.name()).collect();
+    AppError::InvalidFormat(format!("Ícone desconhecido '{}' (use {})", name, names.join(", ")))
+}
@@ -2,31 +2,45 @@
 // src/image_processor.rs - Processamento de imagens e overlay (VERSÃO CORRIGIDA)
 // ============================================================================
 
-use image::{Rgba, RgbaImage, GenericImageView, imageops};
-use imageproc::drawing::{draw_text_mut, text_size};
-use rusttype::{Font, Scale};
-use chrono::Local;
+use image::{DynamicImage, Rgba, RgbaImage, GenericImageView, imageops};
+use rusttype::Scale;
+use serde::Serialize;
+use chrono::{DateTime, FixedOffset};
+use log::{debug, info, warn};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
+use crate::assets::{self, Asset};
 use crate::constants::*;
 use crate::error::{AppResult, AppError};
-use crate::parsers::{ActivityData, tcx, fit};
+use crate::fonts::{FontOptions, FontSet, FontStack};
+use crate::icons::{Icon, IconSet, SportKind};
+use crate::parsers::{self, ActivityData};
+use crate::timezone::{self, TimezoneOverride};
+use crate::analysis::{self, PointStats};
+use crate::photo_metadata::{self, MetadataOptions, PhotoCaptureTime, SourceMetadata};
+use crate::encoder::{self, EncoderOptions, OutputFormat};
+use crate::presets::{self, PresetOptions};
+use crate::output_path::{self, CollisionPolicy, OutputTemplate, TemplateContext};
+use crate::renderer::{FontRole, OverlayRenderer, RasterRenderer, SvgFontMode, SvgRenderer};
+use crate::style::{Palette, Theme, WatermarkPolicy};
+use crate::units::{Locale, Units};
 
 /// Enum para diferentes tipos de linha de estatística
 #[derive(Debug, Clone)]
 enum StatLine {
     Simple {
-        icon: &'static str,
+        icon: Icon,
         text: String,
         color: Rgba<u8>,
     },
     WithSubtext {
-        icon: &'static str,
+        icon: Icon,
         main_text: String,
         sub_text: String,
         main_color: Rgba<u8>,
-        sub_color: Rgba<u8>,
     },
 }
 
@@ -35,9 +49,129 @@ pub struct ImageProcessor {
     image: RgbaImage,
     width: u32,
     height: u32,
-    font: Font<'static>,
-    icon_font: Font<'static>,
+    fonts: FontSet,
+    icons: IconSet,
     output_path: PathBuf,
+    /// Caminho automático: resolvido pelo modelo ao processar a atividade
+    auto_output: bool,
+    output_template: OutputTemplate,
+    /// Substitui o diretório do caminho automático (`--output-dir`)
+    output_dir: Option<PathBuf>,
+    collision_policy: CollisionPolicy,
+    /// Nome da foto sem extensão (no modo adesivo, o nome da atividade)
+    photo_stem: String,
+    /// "stats-overlay" ou "sticker", usado no nome automático
+    output_kind: &'static str,
+    encoder_options: EncoderOptions,
+    timezone: Option<TimezoneOverride>,
+    capture_time: Option<PhotoCaptureTime>,
+    show_photo_point: bool,
+    source_metadata: SourceMetadata,
+    metadata_options: MetadataOptions,
+    describe_activity: bool,
+    /// Falso no modo adesivo: não há foto para embutir no SVG
+    has_photo: bool,
+    svg_font_mode: SvgFontMode,
+    /// Documento gerado quando a saída é SVG (a imagem raster fica sem overlay)
+    svg_document: Option<String>,
+    /// Decisões de layout do último overlay (`--explain`)
+    layout_explanation: Option<LayoutExplanation>,
+    theme: Theme,
+    watermark_policy: WatermarkPolicy,
+    units: Units,
+    locale: Locale,
+}
+
+/// Retângulo em pixels da imagem final
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LayoutRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Decisões tomadas ao posicionar o overlay, exibidas com `--explain`
+#[derive(Debug, Clone, Serialize)]
+pub struct LayoutExplanation {
+    pub image_width: u32,
+    pub image_height: u32,
+    /// Tamanho da fonte: 1/40 da menor dimensão, no mínimo 12 px
+    pub font_scale: f32,
+    pub shadow_offset: i32,
+    pub stat_lines: usize,
+    pub stats: LayoutRect,
+    pub watermark: LayoutRect,
+    /// A marca d'água só é desenhada para dispositivos Garmin
+    pub garmin_device: bool,
+    /// Luminância média (0-255) do fundo sob a marca d'água
+    pub background_luminance: Option<f32>,
+    /// Arquivo da marca d'água escolhido pela política ou pela luminância
+    pub watermark_file: Option<&'static str>,
+    /// Luminância média (0-255) sob as estatísticas, medida com `--theme auto`
+    pub stats_luminance: Option<f32>,
+    /// Tema efetivo: "light" ou "dark"
+    pub theme: &'static str,
+    /// "raster" ou "svg"
+    pub renderer: &'static str,
+    /// Fontes dos rótulos: a escolhida e as substitutas para glifos ausentes
+    pub label_fonts: Vec<String>,
+    /// Fontes dos números
+    pub number_fonts: Vec<String>,
+    /// Conjunto de ícones usado
+    pub icon_set: String,
+}
+
+/// Resultado da gravação da imagem final
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveOutcome {
+    /// Imagem gravada no caminho indicado
+    Written(PathBuf),
+    /// Arquivo já existia e a política de colisão é `skip`
+    Skipped(PathBuf),
+}
+
+/// Tamanho da tela transparente do modo adesivo (ex: "1080x1920")
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanvasSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for CanvasSize {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parsed = value
+            .split_once(['x', 'X'])
+            .and_then(|(w, h)| Some((w.trim().parse::<u32>().ok()?, h.trim().parse::<u32>().ok()?)));
+        match parsed {
+            Some((width, height)) if width > 0 && height > 0 => CanvasSize { width, height }.validate(),
+            _ => Err(format!("Tamanho inválido: '{}'. Use LARGURAxALTURA (ex: 1080x1920)", value)),
+        }
+    }
+}
+
+impl CanvasSize {
+    /// Rejeita telas maiores que `MAX_CANVAS_DIMENSION` em qualquer lado
+    fn validate(self) -> Result<Self, String> {
+        if self.width > MAX_CANVAS_DIMENSION || self.height > MAX_CANVAS_DIMENSION {
+            return Err(format!(
+                "Tela {}x{} grande demais: o limite é {} pixels por lado",
+                self.width, self.height, MAX_CANVAS_DIMENSION
+            ));
+        }
+        Ok(self)
+    }
+
+    /// Rejeita de antemão telas que o formato de saída não consegue codificar
+    /// (WebP aceita no máximo `WEBP_MAX_DIMENSION` pixels por lado)
+    pub fn check_format(self, format: Option<OutputFormat>) -> AppResult<()> {
+        match format {
+            Some(OutputFormat::Webp | OutputFormat::WebpLossy) => encoder::check_webp_dimensions(self.width, self.height),
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Layout para posicionamento do overlay com posições absolutas fixas
@@ -61,76 +195,183 @@ struct OverlayLayout {
     text_line_height: u32,
 }
 
+/// Escala, deslocamento da sombra e cores do texto das estatísticas
+#[derive(Debug, Clone, Copy)]
+struct TextStyle {
+    scale: Scale,
+    shadow_offset: i32,
+    palette: Palette,
+}
+
 impl ImageProcessor {
     /// Cria um novo processador de imagem
     pub fn new(image_path: &PathBuf) -> AppResult<Self> {
-        println!("Carregando imagem: {:?}", image_path);
-        let image = image::open(image_path)?.to_rgba8();
-        let (width, height) = image.dimensions();
+        info!("Carregando imagem: {:?}", image_path);
+        let image_bytes = fs::read(image_path).map_err(AppError::file(image_path))?;
+
+        let photo_stem = image_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| AppError::InvalidFormat("Nome de arquivo inválido".to_string()))?;
+        // Sem --format, a saída automática mantém a extensão da foto (jpg se não houver)
+        let extension = image_path.extension().and_then(|s| s.to_str()).unwrap_or("jpg");
+
+        let format = image::ImageFormat::from_path(image_path).ok();
+        let mut processor = Self::decode(&image_bytes, format)?;
+        processor.set_auto_output(photo_stem, "stats-overlay", extension);
+        Ok(processor)
+    }
+
+    /// Lê a foto da entrada padrão (`-i -`); a saída automática usa o nome
+    /// "stdin-stats-overlay" com a extensão do formato detectado
+    pub fn from_stdin() -> AppResult<Self> {
+        info!("Carregando imagem da entrada padrão");
+        let mut image_bytes = Vec::new();
+        std::io::stdin().lock().read_to_end(&mut image_bytes)?;
+
+        let format = image::guess_format(&image_bytes)?;
+        let extension = format.extensions_str().first().copied().unwrap_or("png");
+
+        let mut processor = Self::decode(&image_bytes, Some(format))?;
+        processor.set_auto_output("stdin", "stats-overlay", extension);
+        Ok(processor)
+    }
+
+    /// Cria um processador a partir dos bytes de uma foto (JPEG, PNG, WebP...),
+    /// aplicando a orientação e lendo os metadados EXIF como em [`ImageProcessor::new`]
+    pub fn from_bytes(image_bytes: &[u8]) -> AppResult<Self> {
+        Self::decode(image_bytes, None)
+    }
+
+    /// Cria um processador a partir de uma imagem já decodificada (sem EXIF)
+    pub fn from_image(image: DynamicImage) -> AppResult<Self> {
+        Self::from_canvas(image.to_rgba8(), true, None, SourceMetadata::default())
+    }
+
+    /// Decodifica a foto, aplicando a orientação EXIF antes do layout (fotos em retrato de celular)
+    fn decode(image_bytes: &[u8], format: Option<image::ImageFormat>) -> AppResult<Self> {
+        let exif = photo_metadata::read_exif(image_bytes);
+        let orientation = exif.as_ref().map_or(1, photo_metadata::read_orientation);
+        debug!("📐 Orientação EXIF: {}", orientation);
+
+        let format = match format {
+            Some(format) => format,
+            None => image::guess_format(image_bytes)?,
+        };
+        let decoded = image::load_from_memory_with_format(image_bytes, format)?;
+        let image = photo_metadata::apply_orientation(decoded, orientation).to_rgba8();
         
-        println!("📐 [DEBUG] Dimensões da imagem carregada: {}x{}", width, height);
+        debug!("📐 Dimensões da imagem carregada: {}x{}", image.width(), image.height());
+
+        let capture_time = exif.as_ref().and_then(photo_metadata::read_capture_time);
+        debug!("📷 Momento de captura (EXIF): {:?}", capture_time);
+        let source_metadata = photo_metadata::read_source_metadata(image_bytes, exif.as_ref());
+
+        Self::from_canvas(image, true, capture_time, source_metadata)
+    }
+
+    /// Cria um processador com uma tela transparente (modo adesivo), sem foto
+    /// de entrada; o overlay usa o mesmo layout e é salvo como PNG com alfa
+    pub fn new_transparent(size: CanvasSize, activity_path: &Path) -> AppResult<Self> {
+        let size = size.validate().map_err(AppError::InvalidFormat)?;
+        info!("Criando tela transparente: {}x{}", size.width, size.height);
+        let image = RgbaImage::from_pixel(size.width, size.height, Rgba([0u8, 0u8, 0u8, 0u8]));
 
-        println!("Carregando fontes...");
-        let font = Self::load_font(FONT_PATH)?;
-        let icon_font = Self::load_font(ICON_FONT_PATH)?;
+        let activity_stem = activity_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .filter(|stem| *stem != STDIO_PATH)
+            .unwrap_or("atividade");
+
+        let mut processor = Self::from_canvas(image, false, None, SourceMetadata::default())?;
+        processor.set_auto_output(activity_stem, "sticker", "png");
+        Ok(processor)
+    }
 
-        // Gera automaticamente o caminho de saída baseado na imagem original
-        let auto_output_path = Self::generate_output_path(image_path)?;
+    /// Monta o processador a partir da imagem já carregada; a saída é
+    /// `DEFAULT_OUTPUT_PATH` até um construtor ativar a saída automática
+    fn from_canvas(
+        image: RgbaImage,
+        has_photo: bool,
+        capture_time: Option<PhotoCaptureTime>,
+        source_metadata: SourceMetadata,
+    ) -> AppResult<Self> {
+        let (width, height) = image.dimensions();
+
+        debug!("Carregando fontes...");
+        let fonts = FontOptions::default().load()?;
 
         Ok(Self {
             image,
             width,
             height,
-            font,
-            icon_font,
-            output_path: auto_output_path,
+            fonts,
+            icons: IconSet::default(),
+            output_path: PathBuf::from(DEFAULT_OUTPUT_PATH),
+            auto_output: false,
+            output_template: OutputTemplate::default(),
+            output_dir: None,
+            collision_policy: CollisionPolicy::default(),
+            photo_stem: String::new(),
+            output_kind: "stats-overlay",
+            encoder_options: EncoderOptions::default(),
+            timezone: None,
+            capture_time,
+            show_photo_point: false,
+            source_metadata,
+            metadata_options: MetadataOptions::default(),
+            describe_activity: false,
+            has_photo,
+            svg_font_mode: SvgFontMode::default(),
+            svg_document: None,
+            layout_explanation: None,
+            theme: Theme::default(),
+            watermark_policy: WatermarkPolicy::default(),
+            units: Units::default(),
+            locale: Locale::default(),
         })
     }
 
-    /// Gera automaticamente o caminho de saída profissional
-    fn generate_output_path(image_path: &PathBuf) -> AppResult<PathBuf> {
-        use chrono::Local;
-        
-        // Obter diretório home do usuário
-        let home_dir = dirs::home_dir()
-            .ok_or_else(|| AppError::InvalidFormat("Não foi possível determinar o diretório home do usuário".to_string()))?;
-        
-        // Criar estrutura: ~/stats_overlay/YYYY-MM-DD/
-        let today = Local::now().format("%Y-%m-%d").to_string();
-        let output_dir = home_dir.join("stats_overlay").join(&today);
-        
-        // Criar diretórios se não existirem
-        std::fs::create_dir_all(&output_dir)?;
-        
-        // Gerar nome do arquivo: nome-original-stats-overlay.ext
-        let original_filename = image_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .ok_or_else(|| AppError::InvalidFormat("Nome de arquivo inválido".to_string()))?;
-            
-        let original_extension = image_path
-            .extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("jpg"); // Default para jpg se não tiver extensão
-            
-        let new_filename = format!("{}-stats-overlay.{}", original_filename, original_extension);
-        let output_path = output_dir.join(&new_filename);
-        
-        println!("📁 Diretório de saída: {:?}", output_dir);
-        println!("📄 Arquivo de saída: {}", new_filename);
-        
-        Ok(output_path)
+    /// Ativa a saída automática; até a atividade ser processada, o caminho é
+    /// provisório (`<foto>-<tipo>.<ext>`) e serve para deduzir o formato
+    fn set_auto_output(&mut self, photo_stem: &str, kind: &'static str, extension: &str) {
+        self.auto_output = true;
+        self.photo_stem = photo_stem.to_string();
+        self.output_kind = kind;
+        self.output_path = PathBuf::from(format!("{}-{}.{}", photo_stem, kind, extension));
+    }
+
+    /// Resolve o caminho automático pelo modelo, com os dados da atividade
+    fn resolve_output_path(&mut self, activity_data: &ActivityData, start_time_local: DateTime<FixedOffset>) -> AppResult<()> {
+        let extension = self.output_path.extension().and_then(|e| e.to_str()).unwrap_or("png").to_string();
+        let context = TemplateContext {
+            photo_stem: &self.photo_stem,
+            kind: self.output_kind,
+            extension: &extension,
+            activity_date: start_time_local,
+            sport: activity_data.sport.as_deref(),
+            device: &activity_data.device_name,
+        };
+        let output_path = self.output_template.render_in(&context, self.output_dir.as_deref())?;
+
+        info!("📁 Diretório de saída: {:?}", output_path.parent().unwrap_or(Path::new("")));
+        info!("📄 Arquivo de saída: {:?}", output_path.file_name().unwrap_or_default());
+        self.output_path = output_path;
+        Ok(())
     }
 
-    /// Carrega uma fonte a partir do caminho especificado
-    fn load_font(path: &str) -> AppResult<Font<'static>> {
-        let font_data = fs::read(path)?;
-        Font::try_from_vec(font_data)
-            .ok_or_else(|| AppError::FontError(format!("Falha ao carregar fonte: {}", path)))
+    /// Troca o nome da foto usado na saída automática (`{photo_stem}`), ex:
+    /// para diferenciar fotos com o mesmo nome em diretórios distintos
+    pub fn set_photo_stem(&mut self, photo_stem: &str) {
+        if self.auto_output {
+            let extension = self.output_path.extension().and_then(|e| e.to_str()).unwrap_or("png").to_string();
+            self.set_auto_output(photo_stem, self.output_kind, &extension);
+        }
     }
 
     /// Define o caminho de saída personalizado
     pub fn set_output_path(&mut self, path: PathBuf) {
+        self.auto_output = false;
         // Se o caminho for um diretório, gera um nome de arquivo padrão
         if path.is_dir() {
             self.output_path = path.join(DEFAULT_OUTPUT_PATH);
@@ -139,98 +380,378 @@ impl ImageProcessor {
         }
     }
 
+    /// Define o modelo da saída automática (ex: `{home}/overlays/{activity_date:%Y/%m}/{photo_stem}.{ext}`)
+    pub fn set_output_template(&mut self, template: OutputTemplate) {
+        self.output_template = template;
+    }
+
+    /// Grava a saída automática neste diretório, mantendo o nome gerado pelo modelo
+    pub fn set_output_dir(&mut self, output_dir: PathBuf) {
+        self.output_dir = Some(output_dir);
+    }
+
+    /// Define o que fazer quando o arquivo de saída já existe
+    pub fn set_collision_policy(&mut self, policy: CollisionPolicy) {
+        self.collision_policy = policy;
+    }
+
+    /// Define as opções do codificador; na saída automática, a extensão
+    /// do arquivo acompanha o formato escolhido
+    pub fn set_encoder_options(&mut self, options: EncoderOptions) {
+        if let (true, Some(format)) = (self.auto_output, options.format) {
+            self.output_path.set_extension(format.extension());
+        }
+        self.encoder_options = options;
+    }
+
+    /// Aplica um preset de rede social (recorte/letterbox e redimensionamento)
+    pub fn apply_preset(&mut self, options: &PresetOptions) {
+        self.image = presets::apply_preset(&self.image, options);
+        (self.width, self.height) = self.image.dimensions();
+        debug!("📐 Dimensões após o preset: {}x{}", self.width, self.height);
+    }
+
+    /// Define o fuso horário usado para exibir o horário da atividade
+    pub fn set_timezone(&mut self, timezone: TimezoneOverride) {
+        self.timezone = Some(timezone);
+    }
+
+    /// Ativa as estatísticas "no momento da foto" (requer data EXIF na imagem)
+    pub fn set_photo_point(&mut self, enabled: bool) {
+        self.show_photo_point = enabled;
+    }
+
+    /// Define quais metadados da foto original são preservados na saída
+    pub fn set_metadata_options(&mut self, keep_metadata: bool, strip_gps: bool, describe_activity: bool) {
+        self.metadata_options.keep_metadata = keep_metadata;
+        self.metadata_options.strip_gps = strip_gps;
+        self.describe_activity = describe_activity;
+    }
+
+    /// Troca as fontes de rótulos e números (arquivo ou família instalada, peso)
+    pub fn set_fonts(&mut self, options: &FontOptions) -> AppResult<()> {
+        self.fonts = options.load()?;
+        self.fonts.icon_set = self.icons.font().cloned().map(FontStack::new);
+        Ok(())
+    }
+
+    /// Troca o conjunto de ícones (ver `IconSet::load`)
+    pub fn set_icons(&mut self, icons: IconSet) {
+        self.fonts.icon_set = icons.font().cloned().map(FontStack::new);
+        self.icons = icons;
+    }
+
+    /// Define as cores do texto e da sombra (`auto`: pelo fundo das estatísticas)
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Define quando e em que cor a marca d'água é desenhada
+    pub fn set_watermark_policy(&mut self, policy: WatermarkPolicy) {
+        self.watermark_policy = policy;
+    }
+
+    /// Define as unidades (métricas ou imperiais) das distâncias, ritmos e altitudes
+    pub fn set_units(&mut self, units: Units) {
+        self.units = units;
+    }
+
+    /// Define o formato regional da data e da hora
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+
+    /// Define como as fontes são incluídas na saída SVG
+    pub fn set_svg_font_mode(&mut self, mode: SvgFontMode) {
+        self.svg_font_mode = mode;
+    }
+
     /// Processa um arquivo de atividade e adiciona o overlay à imagem
     pub fn process_activity_file(&mut self, activity_path: &PathBuf) -> AppResult<()> {
-        let activity_data = self.parse_activity_file(activity_path)?;
-        self.add_overlay(&activity_data)?;
-        Ok(())
+        let activity_data = parsers::parse_activity_file(activity_path)?;
+        self.process_activity(&activity_data)
     }
 
-    /// Determina o tipo de arquivo e chama o parser apropriado
-    fn parse_activity_file(&self, path: &PathBuf) -> AppResult<ActivityData> {
-        match path.extension().and_then(|s| s.to_str()) {
-            Some("tcx") => tcx::parse_tcx(path),
-            Some("fit") => fit::parse_fit(path),
-            _ => Err(AppError::InvalidFormat(
-                "Formato não suportado. Use arquivos .tcx ou .fit".to_string()
-            )),
-        }
+    /// Adiciona o overlay de uma atividade já carregada (usado no modo batch)
+    pub fn process_activity(&mut self, activity_data: &ActivityData) -> AppResult<()> {
+        self.add_overlay(activity_data)
+    }
+
+    /// Caminho onde a imagem final será salva
+    pub fn output_path(&self) -> &Path {
+        &self.output_path
+    }
+
+    /// Imagem atual (com o overlay, depois de processar a atividade)
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    /// Consome o processador e devolve a imagem final
+    pub fn into_image(self) -> RgbaImage {
+        self.image
+    }
+
+    /// Documento SVG gerado quando o formato de saída é SVG
+    pub fn svg_document(&self) -> Option<&str> {
+        self.svg_document.as_deref()
+    }
+
+    /// Decisões de layout do overlay (disponível após `process_activity`)
+    pub fn layout_explanation(&self) -> Option<&LayoutExplanation> {
+        self.layout_explanation.as_ref()
     }
 
     /// Adiciona o overlay de estatísticas à imagem
     fn add_overlay(&mut self, activity_data: &ActivityData) -> AppResult<()> {
-        println!("📐 [DEBUG] Iniciando overlay - Dimensões atuais da imagem: {}x{}", self.width, self.height);
+        debug!("📐 Iniciando overlay - Dimensões atuais da imagem: {}x{}", self.width, self.height);
         
-        // Calcula o tamanho da fonte baseado na menor dimensão da imagem
-        let font_scale = (self.height.min(self.width) as f32 / 40.0).round().max(12.0);
+        // Criamos as linhas de estatísticas
+        let (start_time_local, timezone_source) = timezone::resolve_start_time(activity_data, self.timezone.as_ref());
+        info!("🕒 Horário da atividade: {} ({})", start_time_local.format("%H:%M %:z"), timezone_source);
+        if self.auto_output {
+            self.resolve_output_path(activity_data, start_time_local)?;
+        }
+        if self.describe_activity {
+            self.metadata_options.description = Some(activity_data.summary_text(start_time_local, self.units, self.locale));
+        }
+        let mut stats_lines = Self::build_stats_lines_static(activity_data, start_time_local, self.units, self.locale);
+        if self.show_photo_point {
+            match self.photo_point_stats(activity_data) {
+                Some(point) => stats_lines.push(Self::build_photo_point_line(&point, self.units)),
+                None => warn!("⚠️  Não foi possível localizar o momento da foto na atividade (EXIF ausente ou fora do intervalo)"),
+            }
+        }
+        
+        // Fontes substitutas para caracteres ausentes (acentos, CJK...) antes de medir
+        self.cover_glyphs(&stats_lines);
+
+        // Calcula o tamanho da fonte baseado na menor dimensão da imagem; se o
+        // overlay não couber, reduz a fonte até MIN_FONT_SCALE antes de desistir
+        let mut font_scale = (self.height.min(self.width) as f32 / 40.0).round().max(12.0);
+        let layout = loop {
+            match self.calculate_fixed_layout(&stats_lines, Scale::uniform(font_scale), font_scale) {
+                Err(AppError::LayoutDoesNotFit { .. }) if font_scale > MIN_FONT_SCALE => {
+                    font_scale = (font_scale * 0.9).floor().max(MIN_FONT_SCALE);
+                    debug!("📐 Overlay não coube; tentando fonte {}", font_scale);
+                }
+                result => break result?,
+            }
+        };
         let scale = Scale::uniform(font_scale);
         let shadow_offset = (font_scale / 15.0).round().max(1.0) as i32;
 
-        println!("📐 [DEBUG] Font scale calculado: {}", font_scale);
-        println!("📐 [DEBUG] Shadow offset: {}", shadow_offset);
-
-        // Criamos as linhas de estatísticas
-        let stats_lines = Self::build_stats_lines_static(activity_data);
-        
-        // Calculamos o layout com posicionamento fixo
-        let layout = self.calculate_fixed_layout(&stats_lines, scale, font_scale)?;
+        debug!("📐 Font scale calculado: {}", font_scale);
+        debug!("📐 Shadow offset: {}", shadow_offset);
         
-        println!("📐 [DEBUG] Layout calculado:");
-        println!("   Stats: {}x{} na posição ({}, {})", layout.stats_width, layout.stats_height, layout.stats_x, layout.stats_y);
-        println!("   Watermark: {}x{} na posição ({}, {})", layout.watermark_width, layout.watermark_height, layout.watermark_x, layout.watermark_y);
+        debug!("📐 Layout calculado:");
+        debug!("   Stats: {}x{} na posição ({}, {})", layout.stats_width, layout.stats_height, layout.stats_x, layout.stats_y);
+        debug!("   Watermark: {}x{} na posição ({}, {})", layout.watermark_width, layout.watermark_height, layout.watermark_x, layout.watermark_y);
         
-        // Verificamos se é Garmin e adiciona marca d'água primeiro
+        // Verificamos se é Garmin; a marca d'água é escolhida pela política (e pelo fundo) antes de desenhar
         let is_garmin = Self::is_garmin_device_static(&activity_data.device_name);
-        if is_garmin {
-            self.add_watermark_fixed(&layout)?;
-        }
+        let background_luminance = (is_garmin && self.watermark_policy == WatermarkPolicy::Auto)
+            .then(|| self.background_luminance(&layout));
+        let watermark_asset = match self.watermark_policy {
+            _ if !is_garmin => None,
+            WatermarkPolicy::Auto => background_luminance.map(Self::watermark_for),
+            WatermarkPolicy::White => Some(Asset::WatermarkWhite),
+            WatermarkPolicy::Black => Some(Asset::WatermarkBlack),
+            WatermarkPolicy::None => None,
+        };
+        let watermark = watermark_asset.and_then(|asset| self.load_watermark(asset, &layout));
 
-        // Desenha as estatísticas
-        self.draw_stats_fixed(&stats_lines, &layout, scale, shadow_offset);
+        // Tema: `auto` escolhe as cores pela luminância sob as estatísticas
+        let stats_luminance = (self.theme == Theme::Auto).then(|| {
+            self.region_luminance(layout.stats_x, layout.stats_y, layout.stats_width, layout.stats_height)
+        });
+        let theme = self.theme.resolve(stats_luminance.unwrap_or_default());
+        let style = TextStyle { scale, shadow_offset, palette: theme.palette() };
 
-        println!("📐 [DEBUG] Overlay concluído - Dimensões finais da imagem: {}x{}", self.width, self.height);
+        let is_svg = encoder::output_format_for(&self.output_path, &self.encoder_options) == Some(OutputFormat::Svg);
+        self.layout_explanation = Some(LayoutExplanation {
+            image_width: self.width,
+            image_height: self.height,
+            font_scale,
+            shadow_offset,
+            stat_lines: stats_lines.len(),
+            stats: LayoutRect { x: layout.stats_x, y: layout.stats_y, width: layout.stats_width, height: layout.stats_height },
+            watermark: LayoutRect {
+                x: layout.watermark_x,
+                y: layout.watermark_y,
+                width: layout.watermark_width,
+                height: layout.watermark_height,
+            },
+            garmin_device: is_garmin,
+            background_luminance,
+            watermark_file: watermark_asset.map(Asset::relative_path),
+            stats_luminance,
+            theme: theme.name(),
+            renderer: if is_svg { "svg" } else { "raster" },
+            label_fonts: self.fonts.label.describe(),
+            number_fonts: self.fonts.number.describe(),
+            icon_set: self.icons.describe(),
+        });
+
+        if is_svg {
+            info!("🖋️  Gerando overlay vetorial (SVG)");
+            let mut renderer = SvgRenderer::new(self.width, self.height, &self.fonts, self.svg_font_mode);
+            if self.has_photo {
+                renderer.draw_background(&self.image, self.encoder_options.quality)?;
+            }
+            self.render_overlay(&mut renderer, watermark.as_ref(), &stats_lines, &layout, &style)?;
+            self.svg_document = Some(renderer.finish()?);
+        } else {
+            // A imagem sai temporariamente do processador para o renderizador desenhar nela
+            let mut image = std::mem::take(&mut self.image);
+            let mut renderer = RasterRenderer::new(&mut image, &self.fonts);
+            let result = self.render_overlay(&mut renderer, watermark.as_ref(), &stats_lines, &layout, &style);
+            self.image = image;
+            result?;
+        }
+
+        debug!("📐 Overlay concluído - Dimensões finais da imagem: {}x{}", self.width, self.height);
 
         Ok(())
     }
 
     /// Constrói as linhas de estatísticas com ícones e cores (versão estática)
-    fn build_stats_lines_static(activity_data: &ActivityData) -> Vec<StatLine> {
-        let start_time_local = activity_data.start_time.with_timezone(&Local);
-        
-        vec![
-            StatLine::Simple {
-                icon: ICON_TIME,
-                text: activity_data.format_duration(),
-                color: TIME_COLOR,
-            },
+    fn build_stats_lines_static(
+        activity_data: &ActivityData,
+        start_time_local: DateTime<FixedOffset>,
+        units: Units,
+        locale: Locale,
+    ) -> Vec<StatLine> {
+        let mut lines = Vec::new();
+
+        // Esporte com o ícone do tipo (corrida, ciclismo...), quando o arquivo informa
+        if let Some(sport) = activity_data.sport_label() {
+            lines.push(StatLine::Simple {
+                icon: Icon::Sport(SportKind::from_sport(activity_data.sport.as_deref())),
+                text: sport,
+                color: SPORT_COLOR,
+            });
+        }
+        lines.push(StatLine::Simple {
+            icon: Icon::Time,
+            text: activity_data.format_duration(),
+            color: TIME_COLOR,
+        });
+
+        // Distância e ganho de elevação, quando o arquivo tem os dados
+        if let Some(distance) = activity_data.total_distance_meters().filter(|distance| *distance > 0.0) {
+            lines.push(StatLine::Simple {
+                icon: Icon::Distance,
+                text: units.format_distance(distance),
+                color: DISTANCE_COLOR,
+            });
+        }
+        if let Some(gain) = activity_data.elevation_gain_meters().filter(|gain| *gain > 0.0) {
+            lines.push(StatLine::Simple {
+                icon: Icon::Elevation,
+                text: format!("+{}", units.format_altitude(gain)),
+                color: ELEVATION_COLOR,
+            });
+        }
+
+        // Tempo em movimento e ritmo/velocidade média em movimento (calculados das amostras)
+        if let Some(moving_duration) = activity_data.format_moving_duration() {
+            lines.push(StatLine::Simple {
+                icon: Icon::Moving,
+                text: format!("{} mov", moving_duration),
+                color: MOVING_COLOR,
+            });
+        }
+        if let Some(pace_or_speed) = activity_data.format_moving_pace_or_speed(units) {
+            lines.push(StatLine::Simple {
+                icon: Icon::Pace,
+                text: pace_or_speed,
+                color: PACE_COLOR,
+            });
+        }
+
+        lines.extend([
             StatLine::Simple {
-                icon: ICON_FIRE,
+                icon: Icon::Calories,
                 text: format!("{} kcal", activity_data.calories),
                 color: CALORIES_COLOR,
             },
             StatLine::Simple {
-                icon: ICON_HEART,
+                icon: Icon::HeartRate,
                 text: format!("{} avg", activity_data.avg_hr),
                 color: HR_COLOR,
             },
             StatLine::Simple {
-                icon: ICON_HEART,
+                icon: Icon::HeartRate,
                 text: format!("{} max", activity_data.max_hr),
                 color: HR_COLOR,
             },
+        ]);
+        if let Some(power) = activity_data.avg_power_watts() {
+            lines.push(StatLine::Simple {
+                icon: Icon::Power,
+                text: format!("{} W avg", power),
+                color: POWER_COLOR,
+            });
+        }
+        if let Some(cadence) = activity_data.format_avg_cadence() {
+            lines.push(StatLine::Simple {
+                icon: Icon::Cadence,
+                text: format!("{} avg", cadence),
+                color: CADENCE_COLOR,
+            });
+        }
+        lines.extend([
             StatLine::WithSubtext {
-                icon: ICON_CALENDAR,
-                main_text: start_time_local.format("%H:%M").to_string(),
-                sub_text: start_time_local.format("%d/%m/%Y").to_string(),
+                icon: Icon::Calendar,
+                main_text: locale.format_time(start_time_local),
+                sub_text: locale.format_date(start_time_local),
                 main_color: DATE_COLOR,
-                sub_color: Rgba([180u8, 180u8, 180u8, 255u8]), // Cor mais suave para a data
             },
             StatLine::Simple {
-                icon: ICON_DEVICE,
+                icon: Icon::Device,
                 text: activity_data.device_name.clone(),
                 color: DEVICE_COLOR,
             },
-        ]
+        ]);
+
+        lines
+    }
+
+    /// Localiza o momento de captura da foto na linha do tempo da atividade
+    fn photo_point_stats(&self, activity_data: &ActivityData) -> Option<PointStats> {
+        let capture_time = self.capture_time?;
+        // Sem deslocamento no EXIF, assume que a câmera estava no fuso da atividade
+        let (activity_offset, _) = timezone::resolve_offset(activity_data, activity_data.start_time, self.timezone.as_ref());
+        let captured_at = capture_time.to_utc(activity_offset)?;
+        info!("📷 Foto capturada em {} (UTC)", captured_at.format("%Y-%m-%d %H:%M:%S"));
+        analysis::stats_at(activity_data, captured_at)
+    }
+
+    /// Constrói a linha "neste ponto" com distância, tempo, FC e altitude
+    fn build_photo_point_line(point: &PointStats, units: Units) -> StatLine {
+        let elapsed = ActivityData::format_seconds(point.elapsed_seconds);
+        let mut details = Vec::new();
+        let main_text = match point.distance_meters {
+            Some(distance) => {
+                details.push(elapsed);
+                units.format_distance(distance)
+            }
+            None => elapsed,
+        };
+        if let Some(hr) = point.heart_rate {
+            details.push(format!("{} bpm", hr));
+        }
+        if let Some(altitude) = point.altitude_meters {
+            details.push(units.format_altitude(altitude));
+        }
+
+        StatLine::WithSubtext {
+            icon: Icon::Camera,
+            main_text,
+            sub_text: details.join(" · "),
+            main_color: PHOTO_COLOR,
+        }
     }
 
     /// Calcula o layout com posicionamento absolutamente fixo
@@ -250,8 +771,8 @@ impl ImageProcessor {
         for stat_line in stats_lines {
             match stat_line {
                 StatLine::Simple { icon, text, .. } => {
-                    let (icon_width, _) = text_size(scale, &self.icon_font, icon);
-                    let (text_width, _) = text_size(scale, &self.font, text);
+                    let icon_width = self.icon_width(scale, *icon);
+                    let text_width = self.value_width(scale, text);
                     let total_line_width = icon_width + icon_padding + text_width;
                     if total_line_width > max_line_width {
                         max_line_width = total_line_width;
@@ -259,12 +780,12 @@ impl ImageProcessor {
                     total_height += font_scale as u32 + (padding / 2);
                 },
                 StatLine::WithSubtext { icon, main_text, sub_text, .. } => {
-                    let (icon_width, _) = text_size(scale, &self.icon_font, icon);
-                    let (main_text_width, _) = text_size(scale, &self.font, main_text);
+                    let icon_width = self.icon_width(scale, *icon);
+                    let main_text_width = self.value_width(scale, main_text);
                     
                     // Calcula a largura do subtexto com fonte menor
                     let sub_scale = Scale::uniform(font_scale * 0.75);
-                    let (sub_text_width, _) = text_size(sub_scale, &self.font, sub_text);
+                    let sub_text_width = self.fonts.label.width(sub_scale, sub_text);
                     
                     // A largura total é a maior entre texto principal e subtexto
                     let max_text_width = main_text_width.max(sub_text_width);
@@ -282,8 +803,27 @@ impl ImageProcessor {
         let stats_height = total_height;
         let stats_width = max_line_width as u32;
 
-        // Calcula as dimensões da marca d'água (tentativa com imagem padrão)
-        let (watermark_width, watermark_height) = self.calculate_watermark_dimensions(stats_width)?;
+        // Calcula as dimensões da marca d'água (tentativa com imagem padrão);
+        // sem marca d'água (`--watermark none`) nenhum espaço é reservado
+        let (watermark_width, watermark_height, watermark_gap) = match self.watermark_policy {
+            WatermarkPolicy::None => (0, 0, 0),
+            _ => {
+                let (width, height) = self.calculate_watermark_dimensions(stats_width)?;
+                (width, height, STATS_WATERMARK_GAP)
+            }
+        };
+
+        // Estatísticas e marca d'água precisam caber inteiras dentro das margens
+        let needed_width = stats_width + RIGHT_MARGIN;
+        let needed_height = stats_height + watermark_gap + watermark_height + BOTTOM_MARGIN;
+        if needed_width > self.width || needed_height > self.height {
+            return Err(AppError::LayoutDoesNotFit {
+                needed_width,
+                needed_height,
+                width: self.width,
+                height: self.height,
+            });
+        }
 
         // POSICIONAMENTO FIXO:
         // 1. Marca d'água: sempre no canto inferior direito
@@ -292,13 +832,13 @@ impl ImageProcessor {
 
         // 2. Estatísticas: sempre acima da marca d'água com gap fixo
         let stats_x = self.width.saturating_sub(stats_width + RIGHT_MARGIN);
-        let stats_y = watermark_y.saturating_sub(stats_height + STATS_WATERMARK_GAP);
+        let stats_y = watermark_y.saturating_sub(stats_height + watermark_gap);
 
-        println!("📐 [LAYOUT DEBUG] Cálculos de posicionamento:");
-        println!("   Imagem: {}x{}", self.width, self.height);
-        println!("   Stats calculadas: {}x{}", stats_width, stats_height);
-        println!("   Watermark calculada: {}x{}", watermark_width, watermark_height);
-        println!("   Margens: bottom={}, right={}, gap={}", BOTTOM_MARGIN, RIGHT_MARGIN, STATS_WATERMARK_GAP);
+        debug!("📐 Cálculos de posicionamento:");
+        debug!("   Imagem: {}x{}", self.width, self.height);
+        debug!("   Stats calculadas: {}x{}", stats_width, stats_height);
+        debug!("   Watermark calculada: {}x{}", watermark_width, watermark_height);
+        debug!("   Margens: bottom={}, right={}, gap={}", BOTTOM_MARGIN, RIGHT_MARGIN, watermark_gap);
 
         Ok(OverlayLayout {
             stats_x,
@@ -318,34 +858,28 @@ impl ImageProcessor {
     /// Calcula as dimensões da marca d'água baseado no tamanho das estatísticas
     fn calculate_watermark_dimensions(&self, stats_width: u32) -> AppResult<(u32, u32)> {
         // Tenta abrir uma das imagens de marca d'água para obter as proporções originais
-        let watermark_path = if std::path::Path::new(WATERMARK_WHITE_PATH).exists() {
-            WATERMARK_WHITE_PATH
-        } else if std::path::Path::new(WATERMARK_BLACK_PATH).exists() {
-            WATERMARK_BLACK_PATH
-        } else {
+        let Some(watermark) = assets::load(Asset::WatermarkWhite).or_else(|_| assets::load(Asset::WatermarkBlack)).ok() else {
             // Se não encontrar nenhuma marca d'água, usa dimensões padrão
-            println!("⚠️  [DEBUG] Nenhuma marca d'água encontrada, usando dimensões padrão");
+            debug!("⚠️  Nenhuma marca d'água encontrada, usando dimensões padrão");
             return Ok((stats_width, stats_width / 4)); // Proporção 4:1
         };
 
-        match image::open(watermark_path) {
+        match image::load_from_memory(&watermark.data) {
             Ok(img) => {
                 let (orig_w, orig_h) = img.dimensions();
-                println!("📐 [DEBUG] Marca d'água original: {}x{}", orig_w, orig_h);
+                debug!("📐 Marca d'água original: {}x{}", orig_w, orig_h);
                 
                 // A marca d'água terá a mesma largura que as estatísticas
                 let watermark_width = stats_width;
-                let watermark_height = if orig_w > 0 { 
-                    watermark_width * orig_h / orig_w 
-                } else { 
-                    watermark_width / 4 
-                };
+                let watermark_height = (watermark_width * orig_h)
+                    .checked_div(orig_w)
+                    .unwrap_or(watermark_width / 4);
                 
-                println!("📐 [DEBUG] Marca d'água redimensionada: {}x{}", watermark_width, watermark_height);
+                debug!("📐 Marca d'água redimensionada: {}x{}", watermark_width, watermark_height);
                 Ok((watermark_width, watermark_height))
             },
             Err(e) => {
-                println!("⚠️  [DEBUG] Erro ao abrir marca d'água {}: {}", watermark_path, e);
+                warn!("⚠️  Erro ao abrir marca d'água {}: {}", watermark.source, e);
                 // Usa proporção padrão se não conseguir abrir
                 Ok((stats_width, stats_width / 4))
             }
@@ -354,42 +888,61 @@ impl ImageProcessor {
 
     /// Verifica se o dispositivo é da marca Garmin (versão estática)
     fn is_garmin_device_static(device_name: &str) -> bool {
-        println!("🔍 [GARMIN DEBUG] Verificando dispositivo: '{}'", device_name);
+        debug!("🔍 Verificando dispositivo: '{}'", device_name);
         let device_name_lower = device_name.to_lowercase();
-        println!("🔍 [GARMIN DEBUG] Nome em minúsculas: '{}'", device_name_lower);
+        debug!("🔍 Nome em minúsculas: '{}'", device_name_lower);
         
         for series in GARMIN_SERIES {
             if device_name_lower.contains(series) {
-                println!("✅ [GARMIN DEBUG] Dispositivo Garmin detectado! Contém: '{}'", series);
+                debug!("✅ Dispositivo Garmin detectado! Contém: '{}'", series);
                 return true;
             }
         }
         
-        println!("❌ [GARMIN DEBUG] Dispositivo NÃO é Garmin");
-        println!("🔍 [GARMIN DEBUG] Séries verificadas: {:?}", GARMIN_SERIES);
+        debug!("❌ Dispositivo NÃO é Garmin");
+        debug!("🔍 Séries verificadas: {:?}", GARMIN_SERIES);
         false
     }
 
-    /// Adiciona marca d'água com posicionamento fixo
-    fn add_watermark_fixed(&mut self, layout: &OverlayLayout) -> AppResult<()> {
-        println!("🎯 [DEBUG] Iniciando processo de marca d'água com posicionamento fixo");
-        println!("🎯 [DEBUG] Posição da marca d'água: ({}, {})", layout.watermark_x, layout.watermark_y);
-        println!("🎯 [DEBUG] Dimensões da marca d'água: {}x{}", layout.watermark_width, layout.watermark_height);
+    /// Desenha marca d'água e estatísticas no renderizador escolhido
+    fn render_overlay(
+        &self,
+        renderer: &mut dyn OverlayRenderer,
+        watermark: Option<&RgbaImage>,
+        stats_lines: &[StatLine],
+        layout: &OverlayLayout,
+        style: &TextStyle,
+    ) -> AppResult<()> {
+        if let Some(watermark) = watermark {
+            self.add_watermark_fixed(renderer, watermark, layout)?;
+        }
+        self.draw_stats_fixed(renderer, stats_lines, layout, style)
+    }
 
-        println!("Dispositivo Garmin detectado. Analisando fundo para a marca d'água.");
+    /// Luminância média do fundo na região onde a marca d'água será colocada
+    fn background_luminance(&self, layout: &OverlayLayout) -> f32 {
+        debug!("🎯 Iniciando processo de marca d'água com posicionamento fixo");
+        debug!("🎯 Posição da marca d'água: ({}, {})", layout.watermark_x, layout.watermark_y);
+        debug!("🎯 Dimensões da marca d'água: {}x{}", layout.watermark_width, layout.watermark_height);
+
+        debug!("Dispositivo Garmin detectado. Analisando fundo para a marca d'água.");
 
         // Análise da luminância da região onde a marca d'água será colocada
+        self.region_luminance(layout.watermark_x, layout.watermark_y, layout.watermark_width, layout.watermark_height)
+    }
+
+    /// Luminância média (0-255) de uma região da imagem; 128 se a região for vazia
+    fn region_luminance(&self, x0: u32, y0: u32, width: u32, height: u32) -> f32 {
         let mut total_luminance = 0.0;
         let mut pixel_count = 0;
 
-        let end_x = (layout.watermark_x + layout.watermark_width).min(self.width);
-        let end_y = (layout.watermark_y + layout.watermark_height).min(self.height);
+        let end_x = (x0 + width).min(self.width);
+        let end_y = (y0 + height).min(self.height);
 
-        println!("🎯 [DEBUG] Analisando luminância da região: x={} a {}, y={} a {}", 
-                 layout.watermark_x, end_x, layout.watermark_y, end_y);
+        debug!("🎯 Analisando luminância da região: x={} a {}, y={} a {}", x0, end_x, y0, end_y);
 
-        for x in layout.watermark_x..end_x {
-            for y in layout.watermark_y..end_y {
+        for x in x0..end_x {
+            for y in y0..end_y {
                 let pixel = self.image.get_pixel(x, y);
                 let luminance = 0.2126 * (pixel[0] as f32) + 0.7152 * (pixel[1] as f32) + 0.0722 * (pixel[2] as f32);
                 total_luminance += luminance;
@@ -398,55 +951,83 @@ impl ImageProcessor {
         }
 
         let avg_luminance = if pixel_count > 0 { total_luminance / pixel_count as f32 } else { 128.0 };
-        println!("🎯 [DEBUG] Luminância média: {:.1} (pixels analisados: {})", avg_luminance, pixel_count);
-        
-        let watermark_path_to_use = if avg_luminance < 128.0 {
-            println!("Fundo escuro detectado. Usando marca d'água branca.");
-            WATERMARK_WHITE_PATH
+        debug!("🎯 Luminância média: {:.1} (pixels analisados: {})", avg_luminance, pixel_count);
+        avg_luminance
+    }
+
+    /// Marca d'água branca sobre fundo escuro, preta sobre fundo claro
+    fn watermark_for(luminance: f32) -> Asset {
+        if luminance < 128.0 {
+            debug!("Fundo escuro detectado. Usando marca d'água branca.");
+            Asset::WatermarkWhite
         } else {
-            println!("Fundo claro detectado. Usando marca d'água preta.");
-            WATERMARK_BLACK_PATH
+            debug!("Fundo claro detectado. Usando marca d'água preta.");
+            Asset::WatermarkBlack
+        }
+    }
+
+    /// Carrega a marca d'água e a redimensiona para o layout; `None` se o
+    /// arquivo não for encontrado
+    fn load_watermark(&self, asset: Asset, layout: &OverlayLayout) -> Option<RgbaImage> {
+        debug!("🎯 Tentando carregar marca d'água final: {}", asset.relative_path());
+        let watermark = match assets::load(asset) {
+            Ok(watermark) => watermark,
+            Err(searched) => {
+                warn!("⚠️  Marca d'água '{}' não encontrada (locais pesquisados: {})", asset.relative_path(), searched.join(", "));
+                return None;
+            }
         };
 
-        println!("🎯 [DEBUG] Tentando carregar marca d'água final: {}", watermark_path_to_use);
-
-        if let Ok(watermark_img_orig) = image::open(watermark_path_to_use) {
-            println!("✅ [DEBUG] Marca d'água carregada com sucesso!");
-            let watermark_img = watermark_img_orig.to_rgba8();
-            let resized_watermark = imageops::resize(
-                &watermark_img,
-                layout.watermark_width,
-                layout.watermark_height,
-                imageops::FilterType::Lanczos3
-            );
-            
-            println!("🎯 [DEBUG] Marca d'água redimensionada para: {}x{}", layout.watermark_width, layout.watermark_height);
-            println!("🎯 [DEBUG] Aplicando overlay na posição: ({}, {})", layout.watermark_x, layout.watermark_y);
-            
-            // Verifica bounds antes de aplicar
-            if layout.watermark_x < self.width && layout.watermark_y < self.height {
-                imageops::overlay(
-                    &mut self.image,
-                    &resized_watermark,
-                    layout.watermark_x as i64,
-                    layout.watermark_y as i64
+        match image::load_from_memory(&watermark.data) {
+            Ok(watermark_img_orig) => {
+                debug!("✅ Marca d'água carregada: {}", watermark.source);
+                let watermark_img = watermark_img_orig.to_rgba8();
+                let resized_watermark = imageops::resize(
+                    &watermark_img,
+                    layout.watermark_width,
+                    layout.watermark_height,
+                    imageops::FilterType::Lanczos3
                 );
-                
-                println!("✅ Marca d'água adicionada com sucesso!");
-            } else {
-                println!("🚫 [DEBUG] Marca d'água fora dos limites da imagem!");
+                debug!("🎯 Marca d'água redimensionada para: {}x{}", layout.watermark_width, layout.watermark_height);
+                Some(resized_watermark)
             }
+            Err(e) => {
+                warn!("⚠️  Marca d'água inválida em {}: {}", watermark.source, e);
+                None
+            }
+        }
+    }
+
+    /// Adiciona marca d'água com posicionamento fixo
+    fn add_watermark_fixed(
+        &self,
+        renderer: &mut dyn OverlayRenderer,
+        watermark: &RgbaImage,
+        layout: &OverlayLayout,
+    ) -> AppResult<()> {
+        debug!("🎯 Aplicando overlay na posição: ({}, {})", layout.watermark_x, layout.watermark_y);
+
+        // Verifica bounds antes de aplicar
+        if layout.watermark_x < self.width && layout.watermark_y < self.height {
+            renderer.draw_image(watermark, layout.watermark_x as i64, layout.watermark_y as i64)?;
+            debug!("✅ Marca d'água adicionada com sucesso!");
         } else {
-            println!("❌ [DEBUG] FALHA ao abrir marca d'água final: {}", watermark_path_to_use);
-            println!("Aviso: Imagem da marca d'água não encontrada em '{}'.", watermark_path_to_use);
+            warn!("🚫 Marca d'água fora dos limites da imagem!");
         }
 
         Ok(())
     }
 
     /// Desenha as estatísticas na imagem com posicionamento fixo
-    fn draw_stats_fixed(&mut self, stats_lines: &[StatLine], layout: &OverlayLayout, scale: Scale, shadow_offset: i32) {
-        println!("📝 [DEBUG] Desenhando estatísticas na posição: ({}, {})", layout.stats_x, layout.stats_y);
+    fn draw_stats_fixed(
+        &self,
+        renderer: &mut dyn OverlayRenderer,
+        stats_lines: &[StatLine],
+        layout: &OverlayLayout,
+        style: &TextStyle,
+    ) -> AppResult<()> {
+        debug!("📝 Desenhando estatísticas na posição: ({}, {})", layout.stats_x, layout.stats_y);
+        let TextStyle { scale, shadow_offset, palette } = *style;
         
         let mut y_pos = layout.stats_y as i32;
         let font_scale = scale.x; // Obtém o valor da escala
@@ -454,8 +1035,8 @@ impl ImageProcessor {
         for stat_line in stats_lines {
             match stat_line {
                 StatLine::Simple { icon, text, color } => {
-                    let (icon_width, _) = text_size(scale, &self.icon_font, icon);
-                    let (text_width, _) = text_size(scale, &self.font, text);
+                    let icon_width = self.icon_width(scale, *icon);
+                    let text_width = self.value_width(scale, text);
                     
                     let current_line_width = icon_width + layout.icon_padding + text_width;
                     
@@ -468,23 +1049,23 @@ impl ImageProcessor {
                     // Verifica bounds antes de desenhar
                     if icon_x >= 0 && text_x >= 0 && y_pos >= 0 {
                         // Desenha sombra para melhor legibilidade
-                        draw_text_mut(&mut self.image, SHADOW_COLOR, icon_x + shadow_offset, y_pos + shadow_offset, scale, &self.icon_font, icon);
-                        draw_text_mut(&mut self.image, SHADOW_COLOR, text_x + shadow_offset, y_pos + shadow_offset, scale, &self.font, text);
+                        self.draw_icon(renderer, *icon, icon_x + shadow_offset, y_pos + shadow_offset, scale, palette.shadow)?;
+                        self.draw_value(renderer, text, text_x + shadow_offset, y_pos + shadow_offset, scale, palette.shadow);
 
                         // Desenha texto principal
-                        draw_text_mut(&mut self.image, *color, icon_x, y_pos, scale, &self.icon_font, icon);
-                        draw_text_mut(&mut self.image, TEXT_COLOR, text_x, y_pos, scale, &self.font, text);
+                        self.draw_icon(renderer, *icon, icon_x, y_pos, scale, *color)?;
+                        self.draw_value(renderer, text, text_x, y_pos, scale, palette.text);
                     }
                     
                     y_pos += layout.text_line_height as i32;
                 },
-                StatLine::WithSubtext { icon, main_text, sub_text, main_color, sub_color } => {
-                    let (icon_width, _) = text_size(scale, &self.icon_font, icon);
-                    let (main_text_width, _) = text_size(scale, &self.font, main_text);
+                StatLine::WithSubtext { icon, main_text, sub_text, main_color } => {
+                    let icon_width = self.icon_width(scale, *icon);
+                    let main_text_width = self.value_width(scale, main_text);
                     
                     // Escala menor para o subtexto
                     let sub_scale = Scale::uniform(font_scale * 0.75);
-                    let (sub_text_width, _) = text_size(sub_scale, &self.font, sub_text);
+                    let sub_text_width = self.fonts.label.width(sub_scale, sub_text);
                     
                     // Calcula a largura total considerando a maior largura entre textos
                     let max_text_width = main_text_width.max(sub_text_width);
@@ -503,22 +1084,22 @@ impl ImageProcessor {
                     if icon_x >= 0 && main_text_x >= 0 && y_pos >= 0 {
                         // === DESENHA ÍCONE ===
                         // Sombra do ícone
-                        draw_text_mut(&mut self.image, SHADOW_COLOR, icon_x + shadow_offset, y_pos + shadow_offset, scale, &self.icon_font, icon);
+                        self.draw_icon(renderer, *icon, icon_x + shadow_offset, y_pos + shadow_offset, scale, palette.shadow)?;
                         // Ícone principal
-                        draw_text_mut(&mut self.image, *main_color, icon_x, y_pos, scale, &self.icon_font, icon);
+                        self.draw_icon(renderer, *icon, icon_x, y_pos, scale, *main_color)?;
                         
                         // === DESENHA TEXTO PRINCIPAL (HORÁRIO) ===
                         // Sombra do texto principal
-                        draw_text_mut(&mut self.image, SHADOW_COLOR, main_text_x + shadow_offset, y_pos + shadow_offset, scale, &self.font, main_text);
+                        self.draw_value(renderer, main_text, main_text_x + shadow_offset, y_pos + shadow_offset, scale, palette.shadow);
                         // Texto principal
-                        draw_text_mut(&mut self.image, TEXT_COLOR, main_text_x, y_pos, scale, &self.font, main_text);
+                        self.draw_value(renderer, main_text, main_text_x, y_pos, scale, palette.text);
                         
                         // === DESENHA SUBTEXTO (DATA) ===
                         if sub_text_y >= 0 {
                             // Sombra do subtexto
-                            draw_text_mut(&mut self.image, SHADOW_COLOR, sub_text_x + shadow_offset, sub_text_y + shadow_offset, sub_scale, &self.font, sub_text);
+                            renderer.draw_text(sub_text, sub_text_x + shadow_offset, sub_text_y + shadow_offset, sub_scale, FontRole::Label, palette.shadow);
                             // Subtexto
-                            draw_text_mut(&mut self.image, *sub_color, sub_text_x, sub_text_y, sub_scale, &self.font, sub_text);
+                            renderer.draw_text(sub_text, sub_text_x, sub_text_y, sub_scale, FontRole::Label, palette.sub_text);
                         }
                     }
                     
@@ -528,43 +1109,203 @@ impl ImageProcessor {
             }
         }
         
-        println!("✅ [DEBUG] Estatísticas desenhadas com sucesso!");
+        debug!("✅ Estatísticas desenhadas com sucesso!");
+        Ok(())
     }
 
-    /// Salva a imagem processada
-    pub fn save_result(&self) -> AppResult<()> {
-        println!("📐 [DEBUG] Salvando imagem - Dimensões antes do salvamento: {}x{}", self.width, self.height);
-        println!("Salvando imagem final em: {:?}", self.output_path);
-        
-        // Verifica se o diretório pai existe
-        if let Some(parent) = self.output_path.parent() {
-            if !parent.exists() {
-                std::fs::create_dir_all(parent)?;
+    /// Largura do ícone: imagem do conjunto ou glifo
+    fn icon_width(&self, scale: Scale, icon: Icon) -> i32 {
+        match self.icons.image_size(icon, icon_size(scale)) {
+            Some((width, _)) => width as i32,
+            None => {
+                let (glyph, role) = self.icons.glyph(icon);
+                self.fonts.stack(role).width(scale, glyph)
             }
         }
-        
-        // Verifica se o caminho tem uma extensão válida
-        if self.output_path.extension().is_none() {
+    }
+
+    /// Desenha o ícone com o topo da linha em `(x, y)`. Imagens ficam sobre a
+    /// linha de base do texto, descendo um pouco abaixo dela como os glifos.
+    fn draw_icon(&self, renderer: &mut dyn OverlayRenderer, icon: Icon, x: i32, y: i32, scale: Scale, color: Rgba<u8>) -> AppResult<()> {
+        let size = icon_size(scale);
+        match self.icons.render_image(icon, size, color) {
+            Some(image) => {
+                let baseline = y as f32 + self.fonts.label.primary().v_metrics(scale).ascent;
+                let top = (baseline - size as f32 * 0.85).round() as i64;
+                renderer.draw_image(&image, x as i64, top)
+            }
+            None => {
+                let (glyph, role) = self.icons.glyph(icon);
+                renderer.draw_text(glyph, x, y, scale, role, color);
+                Ok(())
+            }
+        }
+    }
+
+    /// Acrescenta fontes substitutas para os caracteres das estatísticas
+    /// que as fontes escolhidas não têm (ex: nome do dispositivo em CJK)
+    fn cover_glyphs(&mut self, stats_lines: &[StatLine]) {
+        let mut labels = String::new();
+        let mut numbers = String::new();
+        for stat_line in stats_lines {
+            let (main_text, sub_text) = match stat_line {
+                StatLine::Simple { text, .. } => (text, None),
+                StatLine::WithSubtext { main_text, sub_text, .. } => (main_text, Some(sub_text)),
+            };
+            let (value, label) = split_value(main_text);
+            numbers.push_str(value);
+            labels.push_str(label);
+            labels.extend(sub_text.map(String::as_str));
+        }
+        self.fonts.cover(&labels, &numbers);
+    }
+
+    /// Largura de um texto com valor (fonte dos números) e unidade (fonte dos rótulos)
+    fn value_width(&self, scale: Scale, text: &str) -> i32 {
+        match split_value(text) {
+            (value, "") => self.fonts.number.width(scale, value),
+            (value, label) => self.fonts.number.advance(scale, value) + self.fonts.label.width(scale, label),
+        }
+    }
+
+    /// Desenha o valor com a fonte dos números e a unidade logo depois, com
+    /// a fonte dos rótulos na mesma linha de base
+    fn draw_value(&self, renderer: &mut dyn OverlayRenderer, text: &str, x: i32, y: i32, scale: Scale, color: Rgba<u8>) {
+        let (value, label) = split_value(text);
+        if !value.is_empty() {
+            renderer.draw_text(value, x, y, scale, FontRole::Number, color);
+        }
+        if !label.is_empty() {
+            let baseline_shift = self.fonts.number.primary().v_metrics(scale).ascent
+                - self.fonts.label.primary().v_metrics(scale).ascent;
+            let label_x = x + self.fonts.number.advance(scale, value);
+            renderer.draw_text(label, label_x, y + baseline_shift.round() as i32, scale, FontRole::Label, color);
+        }
+    }
+
+    /// Codifica a imagem final em memória, com os metadados configurados.
+    /// O formato vem de `--format` ou da extensão do caminho de saída
+    /// (PNG para processadores criados em memória).
+    pub fn encode(&self) -> AppResult<Vec<u8>> {
+        if let Some(document) = &self.svg_document {
+            return Ok(document.clone().into_bytes());
+        }
+        self.check_transparent_format()?;
+        let encoded = encoder::encode_for_path(&self.image, &self.output_path, &self.encoder_options)?;
+        photo_metadata::embed_metadata(encoded, &self.source_metadata, &self.metadata_options)
+    }
+
+    /// A tela transparente (modo adesivo) só pode ser salva em formatos com alfa
+    fn check_transparent_format(&self) -> AppResult<()> {
+        if self.has_photo {
+            return Ok(());
+        }
+        match encoder::output_format_for(&self.output_path, &self.encoder_options) {
+            Some(format) if format.supports_alpha() => Ok(()),
+            _ => Err(AppError::InvalidFormat(format!(
+                "O modo adesivo precisa de um formato com transparência (png, webp ou svg): {:?}",
+                self.output_path
+            ))),
+        }
+    }
+
+    /// Salva a imagem processada, aplicando a política de colisão
+    /// quando o arquivo de saída já existe
+    pub fn save_result(&self) -> AppResult<SaveOutcome> {
+        debug!("📐 Salvando imagem - Dimensões antes do salvamento: {}x{}", self.width, self.height);
+
+        // Sem --format, a extensão define o formato de saída
+        if self.svg_document.is_none() && self.encoder_options.format.is_none() && self.output_path.extension().is_none() {
             return Err(AppError::InvalidFormat(
                 format!("Caminho de saída deve incluir uma extensão de arquivo (ex: .png, .jpg): {:?}", 
                        self.output_path)
             ));
         }
+
+        self.check_transparent_format()?;
+
+        let Some(output_path) = output_path::resolve_collision(&self.output_path, self.collision_policy)? else {
+            info!("⏭️  {:?} já existe; gravação ignorada (--on-conflict skip)", self.output_path);
+            return Ok(SaveOutcome::Skipped(self.output_path.clone()));
+        };
+        if self.collision_policy == CollisionPolicy::Overwrite && output_path.exists() {
+            info!("♻️  Substituindo arquivo existente: {:?}", output_path);
+        }
+        info!("Salvando imagem final em: {:?}", output_path);
         
+        // Verifica se o diretório pai existe
+        if let Some(parent) = output_path.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        
+        // Saída vetorial: o documento já contém a foto, as fontes e o overlay
+        if let Some(document) = &self.svg_document {
+            if self.metadata_options.is_active() {
+                warn!("⚠️  SVG não suporta EXIF/ICC; metadados não foram gravados");
+            }
+            let Some(output_path) = self.write_output(&output_path, document.as_bytes())? else {
+                return Ok(SaveOutcome::Skipped(self.output_path.clone()));
+            };
+            info!("✅ SVG salvo com sucesso!");
+            return Ok(SaveOutcome::Written(output_path));
+        }
+
         // Log das dimensões da imagem antes de salvar
         let (final_width, final_height) = self.image.dimensions();
-        println!("📐 [DEBUG] Dimensões da imagem no buffer: {}x{}", final_width, final_height);
+        debug!("📐 Dimensões da imagem no buffer: {}x{}", final_width, final_height);
         
-        self.image.save(&self.output_path)
-            .map_err(|e| AppError::ImageError(e))?;
+        // Codifica em memória para poder anexar EXIF/ICC/XMP antes de gravar
+        let output_bytes = self.encode()?;
+        let Some(output_path) = self.write_output(&output_path, &output_bytes)? else {
+            return Ok(SaveOutcome::Skipped(self.output_path.clone()));
+        };
         
         // Verificar o arquivo salvo
-        if let Ok(saved_img) = image::open(&self.output_path) {
+        if let Ok(saved_img) = image::open(&output_path) {
             let (saved_w, saved_h) = saved_img.dimensions();
-            println!("📐 [DEBUG] Dimensões da imagem salva: {}x{}", saved_w, saved_h);
+            debug!("📐 Dimensões da imagem salva: {}x{}", saved_w, saved_h);
         }
         
-        println!("✅ Imagem salva com sucesso!");
-        Ok(())
+        info!("✅ Imagem salva com sucesso!");
+        Ok(SaveOutcome::Written(output_path))
+    }
+
+    /// Grava os bytes de forma atômica. Com `suffix`, parte do caminho pedido
+    /// para reaproveitar o primeiro nome livre mesmo se `resolved` for ocupado
+    /// por outro processo antes da gravação; `None` quando a gravação é pulada.
+    fn write_output(&self, resolved: &Path, data: &[u8]) -> AppResult<Option<PathBuf>> {
+        let target = match self.collision_policy {
+            CollisionPolicy::Suffix => self.output_path.as_path(),
+            _ => resolved,
+        };
+        let written = output_path::write_atomic(target, data, self.collision_policy)?;
+        match &written {
+            Some(path) if path != resolved => info!("🔀 {:?} foi criado durante a gravação; salvo em {:?}", resolved, path),
+            Some(_) => {}
+            None => info!("⏭️  {:?} foi criado durante a gravação; gravação ignorada (--on-conflict skip)", resolved),
+        }
+        Ok(written)
     }
-}
\ No newline at end of file
+}
+
+/// Separa o valor do início do texto (palavras que começam com dígito) da
+/// unidade ou rótulo que o segue, ex: "0h 24m mov" → ("0h 24m", " mov").
+/// Textos que não começam com dígito são só rótulo.
+fn split_value(text: &str) -> (&str, &str) {
+    let (mut start, mut end) = (0, 0);
+    for word in text.split(' ') {
+        if !word.starts_with(|c: char| c.is_ascii_digit()) {
+            break;
+        }
+        end = start + word.len();
+        start = end + 1;
+    }
+    text.split_at(end)
+}
+
+/// Altura das imagens de ícones (85% da escala do texto, como os glifos)
+fn icon_size(scale: Scale) -> u32 {
+    (scale.y * 0.85).round() as u32
+}
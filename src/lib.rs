@@ -0,0 +1,74 @@
+// ============================================================================
+// src/lib.rs - API pública da biblioteca (parsers e renderização do overlay)
+// ============================================================================
+
+//! Leitura de atividades TCX/FIT/GPX e renderização do overlay de estatísticas.
+//!
+//! Uso em memória, sem caminhos nem gravação em disco:
+//!
+//! ```no_run
+//! use tcx_image_overlay::{parse_activity_file, ImageProcessor};
+//!
+//! # fn main() -> tcx_image_overlay::AppResult<()> {
+//! let activity = parse_activity_file(&"treino.fit".into())?;
+//! let photo = std::fs::read("foto.jpg")?;
+//!
+//! let mut processor = ImageProcessor::from_bytes(&photo)?;
+//! processor.process_activity(&activity)?;
+//! let jpeg_bytes = processor.encode()?;
+//! let image = processor.into_image();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! As fontes e marcas d'água padrão vêm embutidas no binário; arquivos em
+//! `~/.local/share/tcx_image_overlay/{fonts,img}` ou em diretórios
+//! registrados com [`add_asset_dir`] têm prioridade. Outras fontes
+//! (arquivo ou família instalada) são escolhidas com [`FontOptions`].
+
+mod parsers;
+mod image_processor;
+mod error;
+mod constants;
+mod assets;
+mod fonts;
+mod icons;
+mod analysis;
+mod timezone;
+mod photo_metadata;
+mod encoder;
+mod presets;
+mod renderer;
+mod output_path;
+mod style;
+mod units;
+
+// Dados da atividade e parsers
+pub use crate::parsers::fit::{parse_fit, parse_fit_bytes, parse_fit_reader};
+pub use crate::parsers::tcx::{parse_tcx, parse_tcx_str};
+pub use crate::parsers::gpx::{parse_gpx, parse_gpx_str};
+pub use crate::parsers::{
+    parse_activity_bytes, parse_activity_file, parse_activity_file_entry, parse_activity_reader, ActivityData,
+    ActivityFormat, Lap, Sample, TimerEvent,
+};
+pub use crate::analysis::{heart_rate_zones, power_zones, HeartRateZone, PowerZone};
+pub use crate::timezone::{resolve_offset, resolve_start_time, TimezoneOverride, TimezoneSource};
+pub use crate::photo_metadata::{photo_capture_time, PhotoCaptureTime};
+
+// Renderização e gravação
+pub use crate::image_processor::{CanvasSize, ImageProcessor, LayoutExplanation, LayoutRect, SaveOutcome};
+pub use crate::error::{AppError, AppResult};
+
+// Opções do processador
+pub use crate::assets::add_search_dir as add_asset_dir;
+pub use crate::encoder::{EncoderOptions, OutputFormat, PngCompression};
+pub use crate::fonts::{FontOptions, FontSpec, FontWeight};
+pub use crate::icons::IconSet;
+pub use crate::output_path::{CollisionPolicy, OutputTemplate, TemplateContext};
+pub use crate::presets::{CropFocus, FitMode, LetterboxFill, OutputPreset, PresetOptions};
+pub use crate::renderer::SvgFontMode;
+pub use crate::style::{Theme, WatermarkPolicy};
+pub use crate::units::{Locale, Units};
+
+// Convenções compartilhadas com a CLI
+pub use crate::constants::{CONFIG_DIR_NAME, DEFAULT_OUTPUT_QUALITY, HR_ZONE_LOWER_PERCENTS, POWER_ZONE_LOWER_PERCENTS, STDIO_PATH};
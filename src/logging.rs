@@ -0,0 +1,141 @@
+// ============================================================================
+// src/logging.rs - Logs no stderr com níveis (-v/-vv/-q), em texto ou JSON
+// ============================================================================
+
+use chrono::Utc;
+use clap::ValueEnum;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde_json::json;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use tcx_image_overlay::{AppError, LayoutExplanation};
+
+/// Formato das linhas de log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogFormat {
+    /// Mensagens legíveis, uma por linha
+    #[default]
+    Text,
+    /// Um objeto JSON por linha (timestamp, level, target, message)
+    Json,
+}
+
+/// Logger da aplicação; a stdout fica livre para imagens e estatísticas
+struct StderrLogger {
+    level: LevelFilter,
+    format: LogFormat,
+}
+
+static LOGGER: OnceLock<StderrLogger> = OnceLock::new();
+
+/// Instala o logger: `-q` mostra só avisos e erros, `-v` inclui depuração
+/// e `-vv` o rastreamento detalhado (ex: campos dos registros FIT)
+pub fn init(verbose: u8, quiet: bool, format: LogFormat) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::Warn,
+        (false, 0) => LevelFilter::Info,
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    let logger = LOGGER.get_or_init(|| StderrLogger { level, format });
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // Dependências (notify, tiny_http...) só aparecem com avisos e erros
+        metadata.level() <= self.level
+            && (metadata.level() <= Level::Warn || metadata.target().starts_with(env!("CARGO_CRATE_NAME")))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = match self.format {
+            LogFormat::Text => record.args().to_string(),
+            LogFormat::Json => json!({
+                "timestamp": Utc::now().to_rfc3339(),
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            })
+            .to_string(),
+        };
+        let _ = writeln!(io::stderr().lock(), "{}", line);
+    }
+
+    fn flush(&self) {
+        let _ = io::stderr().flush();
+    }
+}
+
+/// Formato escolhido em `init` (texto antes da inicialização)
+fn current_format() -> LogFormat {
+    LOGGER.get().map_or(LogFormat::Text, |logger| logger.format)
+}
+
+/// Mostra um erro fatal de forma legível: mensagem, causas mais profundas
+/// (a causa imediata já faz parte da mensagem), código e status de saída
+pub fn report_error(error: &AppError) {
+    let message = error.to_string();
+    let mut causes: Vec<String> = Vec::new();
+    let mut source = error.source().and_then(Error::source);
+    while let Some(cause) = source {
+        // Erros que só repassam a mensagem da causa não acrescentam nada
+        let text = cause.to_string();
+        if !message.contains(&text) && !causes.contains(&text) {
+            causes.push(text);
+        }
+        source = cause.source();
+    }
+
+    let mut stderr = io::stderr().lock();
+    let _ = match current_format() {
+        LogFormat::Text => {
+            let _ = writeln!(stderr, "❌ {}", message);
+            for cause in &causes {
+                let _ = writeln!(stderr, "   causa: {}", cause);
+            }
+            writeln!(stderr, "   (erro: {}, código de saída {})", error.code(), error.exit_code())
+        }
+        LogFormat::Json => writeln!(
+            stderr,
+            "{}",
+            json!({
+                "timestamp": Utc::now().to_rfc3339(),
+                "level": "ERROR",
+                "target": env!("CARGO_CRATE_NAME"),
+                "message": message,
+                "code": error.code(),
+                "exit_code": error.exit_code(),
+                "causes": causes,
+            })
+        ),
+    };
+}
+
+/// Imprime as decisões de layout (`--explain`), independente do nível de log
+pub fn explain(output: &Path, explanation: &LayoutExplanation) {
+    let line = match current_format() {
+        LogFormat::Text => format!(
+            "🔎 Layout de {:?}:\n{}",
+            output,
+            serde_json::to_string_pretty(explanation).unwrap_or_default()
+        ),
+        LogFormat::Json => json!({
+            "timestamp": Utc::now().to_rfc3339(),
+            "level": "EXPLAIN",
+            "target": "explain",
+            "output": output,
+            "layout": explanation,
+        })
+        .to_string(),
+    };
+    let _ = writeln!(io::stderr().lock(), "{}", line);
+}
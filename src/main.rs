@@ -3,33 +3,153 @@
 // ============================================================================
 
 mod cli;
-mod parsers;
-mod image_processor;
-mod error;
-mod constants;
-
-use crate::cli::CliArgs;
-use crate::image_processor::ImageProcessor;
-use crate::error::AppResult;
-use clap::Parser;
-
-fn main() -> AppResult<()> {
-    let args = CliArgs::parse();
-    
-    println!("=== TCX/FIT Image Overlay Tool ===");
-    
-    let mut processor = ImageProcessor::new(&args.image_path)?;
+mod batch;
+mod watch;
+mod stats;
+mod config;
+mod logging;
+mod server;
+
+use crate::cli::{CliArgs, Command};
+use log::info;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use tcx_image_overlay::{
+    add_asset_dir, parse_activity_file_entry, parse_activity_reader, AppError, AppResult, ImageProcessor, OutputFormat,
+    SaveOutcome, STDIO_PATH,
+};
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
+
+fn main() -> ExitCode {
+    let matches = CliArgs::command().get_matches();
+    let args = CliArgs::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    logging::init(args.verbose, args.quiet, args.log_format);
+    match run(args, &matches) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            logging::report_error(&e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+/// Executa o modo escolhido (subcomando ou foto única)
+fn run(mut args: CliArgs, matches: &ArgMatches) -> AppResult<()> {
+    info!("=== TCX/FIT Image Overlay Tool ===");
+
+    // Padrões do arquivo de configuração (e do perfil); flags da CLI prevalecem
+    let settings = config::load(args.config.as_deref(), args.profile.as_deref())?;
+    if let Some(assets_dir) = settings.assets_dir() {
+        add_asset_dir(assets_dir);
+    }
+    let command_matches = matches.subcommand().map_or(matches, |(_, sub_matches)| sub_matches);
+
+    match args.command.take() {
+        Some(Command::Batch(mut batch_args)) => {
+            settings.apply_overlay(&mut batch_args.overlay, command_matches)?;
+            batch_args.output_dir = batch_args.output_dir.or_else(|| settings.output_dir());
+            batch::run(&batch_args)
+        }
+        Some(Command::Watch(mut watch_args)) => {
+            settings.apply_overlay(&mut watch_args.overlay, command_matches)?;
+            watch_args.output_dir = watch_args.output_dir.or_else(|| settings.output_dir());
+            watch::run(&watch_args)
+        }
+        Some(Command::Serve(serve_args)) => {
+            let workers = serve_args.jobs
+                .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+            server::serve(&serve_args.bind, workers)
+        }
+        Some(Command::Stats(mut stats_args)) => {
+            stats_args.max_hr = stats_args.max_hr.or(settings.max_hr());
+            stats_args.hr_zones = stats_args.hr_zones.or_else(|| settings.hr_zones().map(<[u8]>::to_vec));
+            stats_args.ftp = stats_args.ftp.or(settings.ftp());
+            if stats_args.timezone.is_none() {
+                stats_args.timezone = settings.timezone()?;
+            }
+            stats::run(&stats_args)
+        }
+        None => {
+            settings.apply_overlay(&mut args.overlay, matches)?;
+            run_single(args, settings.output_dir())
+        }
+    }
+}
+
+/// Modo padrão: uma foto (ou tela transparente) e um arquivo de atividade.
+/// `-` na imagem, na atividade ou na saída usa a entrada/saída padrão;
+/// `output_dir` (da configuração) troca o diretório da saída automática.
+fn run_single(args: CliArgs, output_dir: Option<PathBuf>) -> AppResult<()> {
+    let activity_path = args.activity_path.expect("clap exige --file sem subcomando");
+
+    let is_stdio = |path: &Path| path.as_os_str() == STDIO_PATH;
+    let image_from_stdin = args.image_path.as_deref().is_some_and(is_stdio);
+    let activity_from_stdin = is_stdio(&activity_path);
+    let output_to_stdout = args.output_path.as_deref().is_some_and(is_stdio);
+
+    if image_from_stdin && activity_from_stdin {
+        return Err(AppError::InvalidFormat(
+            "A entrada padrão (-) só pode ser usada pela imagem ou pela atividade, não pelas duas".to_string()
+        ));
+    }
+    if activity_from_stdin && args.entry.is_some() {
+        return Err(AppError::InvalidFormat(
+            "--entry não se aplica à atividade lida da entrada padrão (-)".to_string()
+        ));
+    }
+    if output_to_stdout && args.overlay.format.is_none() {
+        return Err(AppError::InvalidFormat(
+            "Informe --format ao escrever na saída padrão (ex: --format png)".to_string()
+        ));
+    }
+
+    if let Some(size) = args.sticker {
+        let format = args.overlay.format
+            .or_else(|| args.output_path.as_deref().and_then(OutputFormat::from_path));
+        size.check_format(format)?;
+    }
+
+    let mut processor = match (&args.image_path, args.sticker) {
+        (_, Some(size)) => ImageProcessor::new_transparent(size, &activity_path)?,
+        (Some(_), None) if image_from_stdin => ImageProcessor::from_stdin()?,
+        (Some(image_path), None) => ImageProcessor::new(image_path)?,
+        (None, None) => unreachable!("clap exige --image ou --sticker"),
+    };
+
+    // Preset, fuso, metadados e codificador
+    args.overlay.configure(&mut processor)?;
     
     // Se o usuário especificou uma saída personalizada, usa ela
-    if let Some(custom_output) = args.output_path {
-        println!("📌 Usando saída personalizada: {:?}", custom_output);
+    if let Some(custom_output) = args.output_path.filter(|path| !is_stdio(path)) {
+        info!("📌 Usando saída personalizada: {:?}", custom_output);
         processor.set_output_path(custom_output);
+    } else if let Some(output_dir) = output_dir {
+        // Mantém o nome gerado automaticamente, trocando apenas o diretório
+        processor.set_output_dir(output_dir);
     }
     // Caso contrário, usa a saída automática já configurada
     
-    processor.process_activity_file(&args.activity_path)?;
-    processor.save_result()?;
+    if activity_from_stdin {
+        let activity_data = parse_activity_reader(io::stdin().lock())?;
+        processor.process_activity(&activity_data)?;
+    } else {
+        let activity_data = parse_activity_file_entry(&activity_path, args.entry.as_deref())?;
+        processor.process_activity(&activity_data)?;
+    }
+    if let (true, Some(explanation)) = (args.overlay.explain, processor.layout_explanation()) {
+        logging::explain(processor.output_path(), explanation);
+    }
+
+    if output_to_stdout {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(&processor.encode()?)?;
+        stdout.flush()?;
+    } else if let SaveOutcome::Skipped(_) = processor.save_result()? {
+        return Ok(());
+    }
     
-    println!("✅ Processo concluído com sucesso!");
+    info!("✅ Processo concluído com sucesso!");
     Ok(())
-}
\ No newline at end of file
+}
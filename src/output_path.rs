@@ -0,0 +1,437 @@
+// ============================================================================
+// src/output_path.rs - Modelos de caminho de saída e tratamento de colisões
+// ============================================================================
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, Local};
+use clap::ValueEnum;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::constants::DEFAULT_OUTPUT_TEMPLATE;
+use crate::error::{AppError, AppResult};
+
+/// Placeholders aceitos no modelo
+const PLACEHOLDERS: &[&str] = &["home", "photo_stem", "kind", "ext", "activity_date", "today", "sport", "device"];
+/// Formato padrão de `{activity_date}` e `{today}`
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Trecho do modelo: texto literal ou placeholder com formato opcional
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Placeholder { name: String, format: Option<String> },
+}
+
+/// Modelo do caminho de saída automático, ex:
+/// `{home}/overlays/{activity_date:%Y/%m}/{sport}-{photo_stem}.{ext}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTemplate {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+/// Valores disponíveis para os placeholders
+#[derive(Debug, Clone)]
+pub struct TemplateContext<'a> {
+    /// Nome da foto sem extensão (no modo adesivo, o nome da atividade)
+    pub photo_stem: &'a str,
+    /// "stats-overlay" ou "sticker"
+    pub kind: &'a str,
+    /// Extensão da saída (formato escolhido ou o da foto)
+    pub extension: &'a str,
+    /// Início da atividade no fuso resolvido
+    pub activity_date: DateTime<FixedOffset>,
+    pub sport: Option<&'a str>,
+    pub device: &'a str,
+}
+
+impl Default for OutputTemplate {
+    fn default() -> Self {
+        DEFAULT_OUTPUT_TEMPLATE.parse().expect("modelo padrão válido")
+    }
+}
+
+impl fmt::Display for OutputTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for OutputTemplate {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = value.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                // "{{" e "}}" escapam as chaves
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut body = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        body.push(c);
+                    }
+                    if !closed {
+                        return Err(format!("'{{{}' sem '}}' correspondente no modelo", body));
+                    }
+                    let (name, format) = match body.split_once(':') {
+                        Some((name, format)) => (name.trim(), Some(format.to_string())),
+                        None => (body.trim(), None),
+                    };
+                    if !PLACEHOLDERS.contains(&name) {
+                        return Err(format!(
+                            "Placeholder desconhecido '{{{}}}' no modelo (use {})",
+                            name,
+                            PLACEHOLDERS.iter().map(|p| format!("{{{}}}", p)).collect::<Vec<_>>().join(", ")
+                        ));
+                    }
+                    if let Some(format) = &format {
+                        if !matches!(name, "activity_date" | "today") {
+                            return Err(format!("'{{{}}}' não aceita formato", name));
+                        }
+                        if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                            return Err(format!("Formato de data inválido em '{{{}:{}}}'", name, format));
+                        }
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder { name: name.to_string(), format });
+                }
+                '}' => return Err("'}' sem '{' correspondente no modelo (use '}}' para o caractere)".to_string()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self { source: value.to_string(), segments })
+    }
+}
+
+impl OutputTemplate {
+    /// Monta o caminho substituindo os placeholders; `~` no início vira o diretório home
+    pub fn render(&self, context: &TemplateContext) -> AppResult<PathBuf> {
+        let home = || {
+            dirs::home_dir()
+                .ok_or_else(|| AppError::InvalidFormat("Não foi possível determinar o diretório home do usuário".to_string()))
+        };
+
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Placeholder { name, format } => {
+                    let date_format = format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT);
+                    let value = match name.as_str() {
+                        "home" => home()?.display().to_string(),
+                        "photo_stem" => context.photo_stem.to_string(),
+                        "kind" => context.kind.to_string(),
+                        "ext" => context.extension.to_string(),
+                        "activity_date" => context.activity_date.format(date_format).to_string(),
+                        "today" => Local::now().format(date_format).to_string(),
+                        "sport" => sanitize(&context.sport.unwrap_or("atividade").to_lowercase()),
+                        "device" => sanitize(context.device),
+                        _ => unreachable!("placeholder validado em from_str"),
+                    };
+                    rendered.push_str(&value);
+                }
+            }
+        }
+
+        match rendered.strip_prefix("~/") {
+            Some(rest) => Ok(home()?.join(rest)),
+            None => Ok(PathBuf::from(rendered)),
+        }
+    }
+
+    /// Como [`OutputTemplate::render`]; com `output_dir` (`--output-dir`),
+    /// mantém o nome gerado e troca apenas o diretório
+    pub fn render_in(&self, context: &TemplateContext, output_dir: Option<&Path>) -> AppResult<PathBuf> {
+        let output_path = self.render(context)?;
+        match (output_dir, output_path.file_name()) {
+            (Some(output_dir), Some(file_name)) => Ok(output_dir.join(file_name)),
+            _ => Ok(output_path),
+        }
+    }
+}
+
+/// Troca separadores de diretório e caracteres problemáticos em nomes de arquivo
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control() { '-' } else { c })
+        .collect::<String>()
+        .replace(' ', "_")
+}
+
+/// O que fazer quando o arquivo de saída já existe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum CollisionPolicy {
+    /// Substitui o arquivo existente
+    #[default]
+    Overwrite,
+    /// Acrescenta -1, -2... ao nome
+    Suffix,
+    /// Não grava a nova imagem
+    Skip,
+    /// Interrompe com erro (--no-clobber)
+    Error,
+}
+
+/// Tentativas de nome com sufixo antes de desistir (`--on-conflict suffix`)
+const MAX_SUFFIX_ATTEMPTS: usize = 10_000;
+
+/// Caminho final de acordo com a política; `None` quando a gravação deve ser
+/// pulada. Com `suffix`, é o primeiro nome livre no momento da verificação: a
+/// gravação em [`write_atomic`] tenta o próximo se outro processo o ocupar antes.
+pub fn resolve_collision(path: &Path, policy: CollisionPolicy) -> AppResult<Option<PathBuf>> {
+    if !path.exists() {
+        return Ok(Some(path.to_path_buf()));
+    }
+    match policy {
+        CollisionPolicy::Overwrite => Ok(Some(path.to_path_buf())),
+        CollisionPolicy::Skip => Ok(None),
+        CollisionPolicy::Error => Err(AppError::OutputExists(path.to_path_buf())),
+        CollisionPolicy::Suffix => Ok(suffixed_paths(path).find(|candidate| !candidate.exists())),
+    }
+}
+
+/// `path` seguido de `nome-1.ext`, `nome-2.ext`...
+fn suffixed_paths(path: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let extension = path.extension().and_then(|e| e.to_str());
+    std::iter::once(path.to_path_buf())
+        .chain((1..).map(move |index| {
+            let file_name = match extension {
+                Some(extension) => format!("{}-{}.{}", stem, index, extension),
+                None => format!("{}-{}", stem, index),
+            };
+            path.with_file_name(file_name)
+        }))
+        .take(MAX_SUFFIX_ATTEMPTS)
+}
+
+/// Contador para nomes de arquivos temporários únicos dentro do processo
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Grava `data` em um arquivo temporário no mesmo diretório e o move para
+/// `path` só depois de completo, evitando imagens truncadas em caso de
+/// interrupção. Um arquivo criado em `path` nesse meio tempo só é substituído
+/// com `overwrite`; `skip` retorna `None`, `error` falha com
+/// `AppError::OutputExists` e `suffix` tenta `nome-1.ext`, `nome-2.ext`...
+/// Retorna o caminho efetivamente gravado.
+pub fn write_atomic(path: &Path, data: &[u8], policy: CollisionPolicy) -> AppResult<Option<PathBuf>> {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("saida");
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}-{}.tmp",
+        file_name,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = write_temp(&temp_path, data).and_then(|()| persist(&temp_path, path, policy));
+    if !matches!(result, Ok(Some(_))) {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn write_temp(temp_path: &Path, data: &[u8]) -> AppResult<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(temp_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Move o temporário para o destino de acordo com a política
+fn persist(temp_path: &Path, path: &Path, policy: CollisionPolicy) -> AppResult<Option<PathBuf>> {
+    match policy {
+        CollisionPolicy::Overwrite => {
+            fs::rename(temp_path, path)?;
+            Ok(Some(path.to_path_buf()))
+        }
+        CollisionPolicy::Skip => Ok(persist_new(temp_path, path)?.then(|| path.to_path_buf())),
+        CollisionPolicy::Error => match persist_new(temp_path, path)? {
+            true => Ok(Some(path.to_path_buf())),
+            false => Err(AppError::OutputExists(path.to_path_buf())),
+        },
+        CollisionPolicy::Suffix => {
+            for candidate in suffixed_paths(path) {
+                if persist_new(temp_path, &candidate)? {
+                    return Ok(Some(candidate));
+                }
+            }
+            Err(AppError::OutputExists(path.to_path_buf()))
+        }
+    }
+}
+
+/// Move o temporário para `path` sem substituir um arquivo existente: usa um
+/// hard link, que falha se o destino já existir, e remove o temporário em
+/// seguida. Retorna `false` quando o destino já existe.
+fn persist_new(temp_path: &Path, path: &Path) -> AppResult<bool> {
+    match fs::hard_link(temp_path, path) {
+        Ok(()) => {
+            fs::remove_file(temp_path)?;
+            Ok(true)
+        }
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+        // Sistemas de arquivos sem hard links (ex: FAT): verifica e renomeia
+        Err(_) if !path.exists() => {
+            fs::rename(temp_path, path)?;
+            Ok(true)
+        }
+        Err(_) => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn context() -> TemplateContext<'static> {
+        TemplateContext {
+            photo_stem: "IMG_0001",
+            kind: "stats-overlay",
+            extension: "jpg",
+            activity_date: FixedOffset::west_opt(3 * 3600).unwrap().with_ymd_and_hms(2024, 8, 20, 6, 0, 0).unwrap(),
+            sport: Some("Trail Running"),
+            device: "Forerunner 265",
+        }
+    }
+
+    fn render(template: &str) -> PathBuf {
+        template.parse::<OutputTemplate>().unwrap().render(&context()).unwrap()
+    }
+
+    fn parse_error(template: &str) -> String {
+        template.parse::<OutputTemplate>().unwrap_err()
+    }
+
+    #[test]
+    fn placeholders_are_replaced() {
+        assert_eq!(
+            render("saida/{sport}/{device}-{photo_stem}-{kind}.{ext}"),
+            PathBuf::from("saida/trail_running/Forerunner_265-IMG_0001-stats-overlay.jpg")
+        );
+    }
+
+    #[test]
+    fn doubled_braces_are_escaped() {
+        assert_eq!(render("{{x}}-{photo_stem}.{ext}"), PathBuf::from("{x}-IMG_0001.jpg"));
+        assert_eq!(render("a}}{{b.png"), PathBuf::from("a}{b.png"));
+    }
+
+    #[test]
+    fn unbalanced_braces_are_rejected() {
+        assert!(parse_error("{photo_stem.jpg").contains("sem '}' correspondente"));
+        assert!(parse_error("photo}.jpg").contains("sem '{' correspondente"));
+    }
+
+    #[test]
+    fn unknown_placeholder_is_rejected() {
+        let error = parse_error("{photo}.jpg");
+        assert!(error.contains("Placeholder desconhecido '{photo}'"), "{}", error);
+        assert!(error.contains("{photo_stem}"), "{}", error);
+    }
+
+    #[test]
+    fn date_placeholders_accept_strftime_formats() {
+        assert_eq!(render("{activity_date}/{photo_stem}.{ext}"), PathBuf::from("2024-08-20/IMG_0001.jpg"));
+        assert_eq!(render("{activity_date:%Y/%m}/{photo_stem}.{ext}"), PathBuf::from("2024/08/IMG_0001.jpg"));
+        assert_eq!(render("{activity_date:%d-%H%M}.{ext}"), PathBuf::from("20-0600.jpg"));
+        assert_eq!(render("{today:%Y}.{ext}"), PathBuf::from(format!("{}.jpg", Local::now().format("%Y"))));
+    }
+
+    #[test]
+    fn invalid_date_formats_are_rejected() {
+        assert!(parse_error("{activity_date:%Q}.jpg").contains("Formato de data inválido"));
+        assert!(parse_error("{sport:%Y}.jpg").contains("não aceita formato"));
+    }
+
+    #[test]
+    fn output_dir_keeps_only_the_file_name() {
+        let template: OutputTemplate = "{activity_date:%Y}/{photo_stem}.{ext}".parse().unwrap();
+        let path = template.render_in(&context(), Some(Path::new("/tmp/saida"))).unwrap();
+        assert_eq!(path, PathBuf::from("/tmp/saida/IMG_0001.jpg"));
+    }
+
+    #[test]
+    fn resolve_collision_follows_each_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let free = dir.path().join("livre.jpg");
+        let taken = dir.path().join("foto.jpg");
+        fs::write(&taken, b"original").unwrap();
+        fs::write(dir.path().join("foto-1.jpg"), b"original").unwrap();
+
+        for policy in [CollisionPolicy::Overwrite, CollisionPolicy::Suffix, CollisionPolicy::Skip, CollisionPolicy::Error] {
+            assert_eq!(resolve_collision(&free, policy).unwrap(), Some(free.clone()));
+        }
+        assert_eq!(resolve_collision(&taken, CollisionPolicy::Overwrite).unwrap(), Some(taken.clone()));
+        assert_eq!(resolve_collision(&taken, CollisionPolicy::Suffix).unwrap(), Some(dir.path().join("foto-2.jpg")));
+        assert_eq!(resolve_collision(&taken, CollisionPolicy::Skip).unwrap(), None);
+        assert!(matches!(resolve_collision(&taken, CollisionPolicy::Error), Err(AppError::OutputExists(_))));
+    }
+
+    #[test]
+    fn write_atomic_overwrite_replaces_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("foto.jpg");
+        fs::write(&path, b"original").unwrap();
+
+        assert_eq!(write_atomic(&path, b"novo", CollisionPolicy::Overwrite).unwrap(), Some(path.clone()));
+        assert_eq!(fs::read(&path).unwrap(), b"novo");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1, "temporário deve ser removido");
+    }
+
+    #[test]
+    fn write_atomic_skip_and_error_keep_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("foto.jpg");
+        fs::write(&path, b"original").unwrap();
+
+        assert_eq!(write_atomic(&path, b"novo", CollisionPolicy::Skip).unwrap(), None);
+        assert!(matches!(write_atomic(&path, b"novo", CollisionPolicy::Error), Err(AppError::OutputExists(_))));
+        assert_eq!(fs::read(&path).unwrap(), b"original");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1, "temporário deve ser removido");
+    }
+
+    #[test]
+    fn write_atomic_suffix_retries_the_next_free_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("foto.jpg");
+        let first = write_atomic(&path, b"1", CollisionPolicy::Suffix).unwrap();
+        let second = write_atomic(&path, b"2", CollisionPolicy::Suffix).unwrap();
+        // Nome ocupado por outro processo entre a verificação e a gravação
+        fs::write(dir.path().join("foto-2.jpg"), b"outro").unwrap();
+        let third = write_atomic(&path, b"3", CollisionPolicy::Suffix).unwrap();
+
+        assert_eq!(first, Some(path.clone()));
+        assert_eq!(second, Some(dir.path().join("foto-1.jpg")));
+        assert_eq!(third, Some(dir.path().join("foto-3.jpg")));
+        assert_eq!(fs::read(dir.path().join("foto-2.jpg")).unwrap(), b"outro");
+        assert_eq!(fs::read(dir.path().join("foto-3.jpg")).unwrap(), b"3");
+    }
+}
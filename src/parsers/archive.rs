@@ -0,0 +1,274 @@
+// ============================================================================
+// src/parsers/archive.rs - Descompressão de atividades exportadas (.gz, .zip)
+// ============================================================================
+
+use flate2::read::GzDecoder;
+use log::info;
+use std::borrow::Cow;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use crate::error::{AppError, AppResult};
+use super::{ActivityFormat, SNIFF_XML_BYTES};
+
+/// Assinatura do gzip
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+/// Assinatura de um arquivo zip (cabeçalho local)
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+/// Níveis de compressão aninhados aceitos (ex: .zip dentro de .gz)
+const MAX_NESTING: usize = 2;
+/// Tamanho máximo de uma atividade descompactada
+const MAX_DECOMPRESSED_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Descompacta o conteúdo se for gzip ou zip; caso contrário devolve os bytes
+/// originais. `name` é o nome do arquivo de entrada (se conhecido) e é usado
+/// para escolher a entrada do zip e deduzir a extensão do conteúdo; `entry`
+/// escolhe explicitamente a entrada do zip (nome completo ou só o do arquivo).
+/// Retorna os bytes da atividade e o nome do arquivo interno.
+pub fn decompress<'a>(
+    data: &'a [u8],
+    name: Option<&str>,
+    entry: Option<&str>,
+) -> AppResult<(Cow<'a, [u8]>, Option<String>)> {
+    let mut current: Cow<'a, [u8]> = Cow::Borrowed(data);
+    let mut current_name = name.map(str::to_string);
+    let mut entry_used = false;
+
+    for _ in 0..MAX_NESTING {
+        if current.starts_with(GZIP_MAGIC) {
+            let decoded = read_bounded(GzDecoder::new(current.as_ref()), "gzip")?;
+            info!("🗜️  Atividade descompactada (gzip): {} bytes", decoded.len());
+            // "treino.fit.gz" → "treino.fit"
+            current_name = current_name.map(|n| n.strip_suffix(".gz").or_else(|| n.strip_suffix(".GZ")).unwrap_or(&n).to_string());
+            current = Cow::Owned(decoded);
+        } else if current.starts_with(ZIP_MAGIC) {
+            let wanted = if entry_used { None } else { entry };
+            entry_used = true;
+            let (entry_name, decoded) = extract_zip_entry(&current, current_name.as_deref(), wanted)?;
+            info!("🗜️  Atividade extraída do zip: {} ({} bytes)", entry_name, decoded.len());
+            current_name = Some(entry_name);
+            current = Cow::Owned(decoded);
+        } else {
+            break;
+        }
+    }
+
+    if let (Some(entry), false) = (entry, entry_used) {
+        return Err(AppError::InvalidFormat(format!(
+            "A entrada '{}' foi informada, mas a atividade não é um arquivo zip", entry
+        )));
+    }
+
+    Ok((current, current_name))
+}
+
+/// Extrai a atividade de um zip: a entrada `wanted`, se informada; senão a
+/// única entrada TCX/FIT/GPX ou, havendo várias, aquela cujo nome contém o nome
+/// do próprio zip (ex: "123.zip" → "123_ACTIVITY.fit"). Só a entrada escolhida
+/// é lida por inteiro, e nunca além de `MAX_DECOMPRESSED_BYTES`.
+fn extract_zip_entry(data: &[u8], zip_name: Option<&str>, wanted: Option<&str>) -> AppResult<(String, Vec<u8>)> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .map_err(|e| AppError::ParseError(format!("Zip inválido: {}", e)))?;
+
+    // (índice, nome) das entradas candidatas
+    let mut candidates: Vec<(usize, String)> = Vec::new();
+    let mut all_names = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(zip_error)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let entry_name = entry.name().to_string();
+        all_names.push(entry_name.clone());
+
+        let is_candidate = match wanted {
+            Some(wanted) => entry_name == wanted
+                || Path::new(&entry_name).file_name().and_then(|n| n.to_str()) == Some(wanted),
+            None => is_activity_entry(&mut entry, &entry_name)?,
+        };
+        if is_candidate {
+            candidates.push((index, entry_name));
+        }
+    }
+
+    let (index, entry_name) = match (wanted, candidates.len()) {
+        (_, 1) => candidates.remove(0),
+        (Some(wanted), 0) => {
+            return Err(AppError::InvalidFormat(format!(
+                "Entrada '{}' não encontrada no zip (entradas: {})", wanted, all_names.join(", ")
+            )));
+        }
+        (Some(wanted), _) => {
+            return Err(AppError::InvalidFormat(format!(
+                "Há várias entradas '{}' no zip; informe o caminho completo ({})",
+                wanted,
+                candidates.iter().map(|(_, name)| name.as_str()).collect::<Vec<_>>().join(", ")
+            )));
+        }
+        (None, 0) => {
+            return Err(AppError::InvalidFormat("Nenhuma atividade TCX/FIT/GPX encontrada no zip".to_string()));
+        }
+        (None, _) => {
+            let zip_stem = zip_name
+                .and_then(|n| Path::new(n).file_stem()?.to_str().map(str::to_lowercase))
+                .unwrap_or_default();
+            let matching: Vec<usize> = candidates.iter()
+                .enumerate()
+                .filter(|(_, (_, entry_name))| !zip_stem.is_empty() && entry_name.to_lowercase().contains(&zip_stem))
+                .map(|(position, _)| position)
+                .collect();
+            match matching[..] {
+                [position] => candidates.swap_remove(position),
+                _ => {
+                    let names: Vec<&str> = candidates.iter().map(|(_, name)| name.as_str()).collect();
+                    return Err(AppError::InvalidFormat(format!(
+                        "O zip contém várias atividades ({}); escolha uma com --entry",
+                        names.join(", ")
+                    )));
+                }
+            }
+        }
+    };
+
+    let entry = archive.by_index(index).map_err(zip_error)?;
+    let contents = read_bounded(entry, &entry_name)?;
+    Ok((entry_name, contents))
+}
+
+/// Decide se uma entrada do zip é uma atividade: pela extensão (`.tcx`,
+/// `.fit`, `.gpx`, `.gz`) ou, sem extensão conhecida, pelos primeiros bytes
+fn is_activity_entry<R: Read>(entry: &mut R, entry_name: &str) -> AppResult<bool> {
+    let path = Path::new(entry_name);
+    let is_gzip_name = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("gz"));
+    if ActivityFormat::from_extension(path).is_some() || is_gzip_name {
+        return Ok(true);
+    }
+
+    let mut head = Vec::new();
+    entry.take(SNIFF_XML_BYTES as u64).read_to_end(&mut head)?;
+    Ok(ActivityFormat::sniff(&head).is_some() || head.starts_with(GZIP_MAGIC))
+}
+
+/// Lê até `MAX_DECOMPRESSED_BYTES`; um conteúdo maior é recusado (bomba de
+/// descompressão) em vez de esgotar a memória
+fn read_bounded<R: Read>(reader: R, what: &str) -> AppResult<Vec<u8>> {
+    let mut contents = Vec::new();
+    reader.take(MAX_DECOMPRESSED_BYTES + 1)
+        .read_to_end(&mut contents)
+        .map_err(|e| AppError::ParseError(format!("Falha ao descompactar {}: {}", what, e)))?;
+    if contents.len() as u64 > MAX_DECOMPRESSED_BYTES {
+        return Err(AppError::InvalidFormat(format!(
+            "A atividade descompactada ({}) passa de {} MB", what, MAX_DECOMPRESSED_BYTES / (1024 * 1024)
+        )));
+    }
+    Ok(contents)
+}
+
+fn zip_error(e: zip::result::ZipError) -> AppError {
+    AppError::ParseError(format!("Falha ao ler o zip: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    const TCX: &[u8] = b"<?xml version=\"1.0\"?><TrainingCenterDatabase></TrainingCenterDatabase>";
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in entries {
+            writer.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn invalid_format(result: AppResult<(Cow<[u8]>, Option<String>)>) -> String {
+        match result {
+            Err(AppError::InvalidFormat(message)) => message,
+            Err(other) => panic!("erro inesperado: {}", other),
+            Ok((_, name)) => panic!("esperava erro, extraiu {:?}", name),
+        }
+    }
+
+    #[test]
+    fn plain_data_is_returned_unchanged() {
+        let (data, name) = decompress(TCX, Some("treino.tcx"), None).unwrap();
+        assert!(matches!(data, Cow::Borrowed(_)));
+        assert_eq!(name.as_deref(), Some("treino.tcx"));
+    }
+
+    #[test]
+    fn gzip_is_decompressed_and_loses_the_suffix() {
+        let archive = gzip(TCX);
+        let (data, name) = decompress(&archive, Some("treino.tcx.gz"), None).unwrap();
+        assert_eq!(data.as_ref(), TCX);
+        assert_eq!(name.as_deref(), Some("treino.tcx"));
+    }
+
+    #[test]
+    fn zip_with_a_single_activity_picks_it() {
+        let archive = zip(&[("LEIAME.txt", b"exportado pelo Garmin Connect"), ("atividades/treino.tcx", TCX)]);
+        let (data, name) = decompress(&archive, Some("export.zip"), None).unwrap();
+        assert_eq!(data.as_ref(), TCX);
+        assert_eq!(name.as_deref(), Some("atividades/treino.tcx"));
+    }
+
+    #[test]
+    fn zip_inside_gzip_is_extracted() {
+        let archive = gzip(&zip(&[("treino.tcx", TCX)]));
+        let (data, name) = decompress(&archive, Some("export.zip.gz"), None).unwrap();
+        assert_eq!(data.as_ref(), TCX);
+        assert_eq!(name.as_deref(), Some("treino.tcx"));
+    }
+
+    #[test]
+    fn zip_with_several_activities_matches_the_zip_stem() {
+        let archive = zip(&[("456_ACTIVITY.fit", b"456"), ("123_ACTIVITY.fit", b"123")]);
+        let (data, name) = decompress(&archive, Some("123.zip"), None).unwrap();
+        assert_eq!(data.as_ref(), b"123");
+        assert_eq!(name.as_deref(), Some("123_ACTIVITY.fit"));
+    }
+
+    #[test]
+    fn ambiguous_zip_asks_for_entry() {
+        let archive = zip(&[("456_ACTIVITY.fit", b"456"), ("123_ACTIVITY.fit", b"123")]);
+        let message = invalid_format(decompress(&archive, Some("export.zip"), None));
+        assert!(message.contains("--entry"), "{}", message);
+    }
+
+    #[test]
+    fn entry_chooses_by_full_path_or_file_name() {
+        let archive = zip(&[("a/456_ACTIVITY.fit", b"456"), ("a/123_ACTIVITY.fit", b"123")]);
+        let (data, _) = decompress(&archive, Some("export.zip"), Some("456_ACTIVITY.fit")).unwrap();
+        assert_eq!(data.as_ref(), b"456");
+        let (data, _) = decompress(&archive, Some("export.zip"), Some("a/123_ACTIVITY.fit")).unwrap();
+        assert_eq!(data.as_ref(), b"123");
+
+        let message = invalid_format(decompress(&archive, Some("export.zip"), Some("789_ACTIVITY.fit")));
+        assert!(message.contains("não encontrada"), "{}", message);
+    }
+
+    #[test]
+    fn entry_on_a_non_zip_is_rejected() {
+        let message = invalid_format(decompress(&gzip(TCX), Some("treino.tcx.gz"), Some("treino.tcx")));
+        assert!(message.contains("não é um arquivo zip"), "{}", message);
+    }
+
+    #[test]
+    fn decompression_bomb_is_refused() {
+        // Menos de 2 MB de gzip que se expandem além do limite
+        let bomb = gzip(&vec![0; MAX_DECOMPRESSED_BYTES as usize + 1]);
+        assert!(bomb.len() < 2 * 1024 * 1024);
+        let message = invalid_format(decompress(&bomb, Some("bomba.fit.gz"), None));
+        assert!(message.contains("passa de"), "{}", message);
+    }
+}
@@ -2,46 +2,67 @@
 // src/parsers/fit.rs - Parser para arquivos FIT
 // ============================================================================
 
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, Offset, TimeZone, Utc};
 use fitparser::{FitDataRecord, Value};
+use log::{debug, trace};
 use std::fs;
+use std::io::Read;
 use std::path::PathBuf;
 use crate::error::{AppResult, AppError};
-use super::ActivityData;
+use super::{ActivityData, Lap, Sample, TimerEvent};
+
+/// Fator de conversão de semicírculos (FIT) para graus
+const SEMICIRCLES_TO_DEGREES: f64 = 180.0 / 2_147_483_648.0;
 
 /// Faz o parsing de um arquivo FIT e retorna os dados da atividade
 pub fn parse_fit(path: &PathBuf) -> AppResult<ActivityData> {
-    println!("Lendo arquivo FIT: {:?}", path);
+    debug!("Lendo arquivo FIT: {:?}", path);
     
-    let data = fs::read(path)?;
+    let data = fs::read(path).map_err(AppError::file(path))?;
+    parse_fit_bytes(&data)
+}
+
+/// Faz o parsing de um FIT lido de qualquer fonte (stdin, arquivos compactados, uploads)
+pub fn parse_fit_reader<R: Read>(mut reader: R) -> AppResult<ActivityData> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    parse_fit_bytes(&data)
+}
+
+/// Faz o parsing do conteúdo binário de um arquivo FIT
+pub fn parse_fit_bytes(data: &[u8]) -> AppResult<ActivityData> {
     let mut session_data: Option<FitDataRecord> = None;
     let mut device_name = "Dispositivo desconhecido".to_string();
     let mut records: Vec<FitDataRecord> = Vec::new();
+    let mut samples: Vec<Sample> = Vec::new();
+    let mut laps: Vec<Lap> = Vec::new();
+    let mut timer_events: Vec<TimerEvent> = Vec::new();
+    let mut utc_offset_seconds: Option<i32> = None;
 
     // Coleta todos os registros
-    for record in fitparser::from_bytes(&data)
-        .map_err(|e| AppError::ParseError(format!("Erro ao ler arquivo FIT: {}", e)))? {
+    for record in fitparser::from_bytes(data)? {
         records.push(record);
     }
 
-    println!("🔍 [FIT DEBUG] Total de registros encontrados: {}", records.len());
+    debug!("🔍 Total de registros encontrados: {}", records.len());
 
     // Processa os registros para encontrar Session e DeviceInfo
     for record in records {
         match record.kind() {
             fitparser::profile::MesgNum::Session => {
-                println!("✅ [FIT DEBUG] Registro de sessão encontrado");
+                debug!("✅ Registro de sessão encontrado");
                 session_data = Some(record);
             }
             fitparser::profile::MesgNum::DeviceInfo => {
-                println!("🔍 [FIT DEBUG] Registro de DeviceInfo encontrado");
+                debug!("🔍 Registro de DeviceInfo encontrado");
                 for field in record.fields() {
-                    println!("   Campo: {} = {:?}", field.name(), field.value());
+                    trace!("   Campo: {} = {:?}", field.name(), field.value());
                     
                     // Prioriza product_name se disponível
                     if field.name() == "product_name" {
                         if let Value::String(name) = field.value() {
                             device_name = name.clone();
-                            println!("✅ [FIT DEBUG] Nome do produto encontrado: '{}'", device_name);
+                            debug!("✅ Nome do produto encontrado: '{}'", device_name);
                         }
                     }
                     // Se não tem product_name, tenta manufacturer + product_name combinados
@@ -49,7 +70,7 @@ pub fn parse_fit(path: &PathBuf) -> AppResult<ActivityData> {
                         if let Value::String(manufacturer) = field.value() {
                             if device_name == "Dispositivo desconhecido" {
                                 device_name = manufacturer.clone();
-                                println!("🔍 [FIT DEBUG] Fabricante encontrado: '{}'", device_name);
+                                debug!("🔍 Fabricante encontrado: '{}'", device_name);
                             }
                         }
                     }
@@ -57,20 +78,44 @@ pub fn parse_fit(path: &PathBuf) -> AppResult<ActivityData> {
                     else if field.name() == "device_type" && device_name == "Dispositivo desconhecido" {
                         if let Value::String(dev_type) = field.value() {
                             device_name = dev_type.clone();
-                            println!("🔍 [FIT DEBUG] Tipo de dispositivo encontrado: '{}'", device_name);
+                            debug!("🔍 Tipo de dispositivo encontrado: '{}'", device_name);
                         }
                     }
                 }
             }
+            fitparser::profile::MesgNum::Record => {
+                if let Some(sample) = sample_from_record(&record) {
+                    samples.push(sample);
+                }
+            }
+            fitparser::profile::MesgNum::Lap => {
+                if let Some(lap) = lap_from_record(&record) {
+                    laps.push(lap);
+                }
+            }
+            fitparser::profile::MesgNum::Activity => {
+                utc_offset_seconds = utc_offset_from_activity(&record);
+                debug!("🔍 Deslocamento do horário local: {:?} s", utc_offset_seconds);
+            }
+            fitparser::profile::MesgNum::Event => {
+                if let Some(event) = timer_event_from_record(&record) {
+                    timer_events.push(event);
+                }
+            }
             _ => {} // Ignora outros tipos de mensagem
         }
     }
 
-    println!("🎯 [FIT DEBUG] Nome final do dispositivo antes da normalização: '{}'", device_name);
+    samples.sort_by_key(|sample| sample.timestamp);
+    laps.sort_by_key(|lap| lap.start_time);
+    timer_events.sort_by_key(|event| match *event {
+        TimerEvent::Start(time) | TimerEvent::Stop(time) => time,
+    });
+
+    debug!("🎯 Nome final do dispositivo antes da normalização: '{}'", device_name);
 
     // Verifica se encontrou dados de sessão
-    let session = session_data.ok_or_else(|| 
-        AppError::ParseError("Dados de sessão não encontrados no arquivo FIT".to_string()))?;
+    let session = session_data.ok_or(AppError::MissingField { format: "FIT", field: "session" })?;
 
     // Função auxiliar para extrair campos da sessão
     let get_field = |name: &str| -> Option<Value> {
@@ -82,8 +127,7 @@ pub fn parse_fit(path: &PathBuf) -> AppResult<ActivityData> {
     // Extrai o timestamp de início
     let start_time = match get_field("start_time") {
         Some(Value::Timestamp(dt)) => dt,
-        _ => return Err(AppError::ParseError(
-            "Campo 'start_time' não encontrado no arquivo FIT".to_string())),
+        _ => return Err(AppError::MissingField { format: "FIT", field: "start_time" }),
     };
 
     // Constrói os dados da atividade
@@ -111,11 +155,144 @@ pub fn parse_fit(path: &PathBuf) -> AppResult<ActivityData> {
         }),
         start_time: start_time.into(),
         device_name,
+        sport: match get_field("sport") {
+            Some(Value::String(sport)) => Some(sport),
+            _ => None,
+        },
+        utc_offset_seconds,
+        moving_time_seconds: None,
+        moving_distance_meters: None,
+        laps,
+        samples,
+        timer_events,
     };
 
     // Normaliza o nome do dispositivo
     activity_data.normalize_device_name();
-    println!("FIT - Nome do dispositivo normalizado: '{}'", activity_data.device_name);
+    debug!("FIT - Nome do dispositivo normalizado: '{}'", activity_data.device_name);
+
+    activity_data.compute_moving_stats();
 
     Ok(activity_data)
+}
+
+/// Converte um valor numérico do FIT para f64
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match *value {
+        Value::Float64(val) => Some(val),
+        Value::Float32(val) => Some(val as f64),
+        Value::UInt8(val) | Value::UInt8z(val) | Value::Byte(val) => Some(val as f64),
+        Value::SInt8(val) => Some(val as f64),
+        Value::UInt16(val) | Value::UInt16z(val) => Some(val as f64),
+        Value::SInt16(val) => Some(val as f64),
+        Value::UInt32(val) | Value::UInt32z(val) => Some(val as f64),
+        Value::SInt32(val) => Some(val as f64),
+        Value::UInt64(val) | Value::UInt64z(val) => Some(val as f64),
+        Value::SInt64(val) => Some(val as f64),
+        _ => None,
+    }
+}
+
+/// Extrai o timestamp de um registro FIT, se presente
+fn record_timestamp(record: &FitDataRecord) -> Option<DateTime<Utc>> {
+    record.fields().iter()
+        .find(|f| f.name() == "timestamp")
+        .and_then(|f| match f.value() {
+            Value::Timestamp(dt) => Some(dt.with_timezone(&Utc)),
+            _ => None,
+        })
+}
+
+/// Converte uma mensagem Record em uma amostra da série temporal
+fn sample_from_record(record: &FitDataRecord) -> Option<Sample> {
+    let mut sample = Sample {
+        timestamp: record_timestamp(record)?,
+        ..Sample::default()
+    };
+
+    for field in record.fields() {
+        let value = field.value();
+        match field.name() {
+            "distance" => sample.distance_meters = value_as_f64(value),
+            // enhanced_* têm prioridade sobre os campos de 16 bits
+            "enhanced_speed" => sample.speed_mps = value_as_f64(value),
+            "speed" if sample.speed_mps.is_none() => sample.speed_mps = value_as_f64(value),
+            "enhanced_altitude" => sample.altitude_meters = value_as_f64(value),
+            "altitude" if sample.altitude_meters.is_none() => sample.altitude_meters = value_as_f64(value),
+            "heart_rate" => sample.heart_rate = value_as_f64(value).map(|hr| hr as u8),
+            "power" => sample.power_watts = value_as_f64(value).map(|watts| watts as u16),
+            "cadence" => sample.cadence = value_as_f64(value).map(|cadence| cadence as u8),
+            "position_lat" => sample.latitude = value_as_f64(value).map(|v| v * SEMICIRCLES_TO_DEGREES),
+            "position_long" => sample.longitude = value_as_f64(value).map(|v| v * SEMICIRCLES_TO_DEGREES),
+            _ => {}
+        }
+    }
+
+    Some(sample)
+}
+
+/// Converte uma mensagem Lap no resumo da volta
+fn lap_from_record(record: &FitDataRecord) -> Option<Lap> {
+    let get_f64 = |name: &str| record.fields().iter()
+        .find(|f| f.name() == name)
+        .and_then(|f| value_as_f64(f.value()));
+    let start_time = record.fields().iter()
+        .find(|f| f.name() == "start_time")
+        .and_then(|f| match f.value() {
+            Value::Timestamp(dt) => Some(dt.with_timezone(&Utc)),
+            _ => None,
+        })?;
+
+    Some(Lap {
+        start_time,
+        total_time_seconds: get_f64("total_elapsed_time").unwrap_or(0.0),
+        distance_meters: get_f64("total_distance"),
+        calories: get_f64("total_calories").map(|v| v as u16),
+        avg_hr: get_f64("avg_heart_rate").map(|v| v as u8),
+        max_hr: get_f64("max_heart_rate").map(|v| v as u8),
+    })
+}
+
+/// Converte uma mensagem Event de cronômetro (timer) em evento de start/stop
+fn timer_event_from_record(record: &FitDataRecord) -> Option<TimerEvent> {
+    let get_string = |name: &str| record.fields().iter()
+        .find(|f| f.name() == name)
+        .and_then(|f| match f.value() {
+            Value::String(val) => Some(val.clone()),
+            _ => None,
+        });
+
+    if get_string("event").as_deref() != Some("timer") {
+        return None;
+    }
+
+    let timestamp = record_timestamp(record)?;
+    match get_string("event_type")?.as_str() {
+        "start" => Some(TimerEvent::Start(timestamp)),
+        "stop" | "stop_all" | "stop_disable" | "stop_disable_all" => Some(TimerEvent::Stop(timestamp)),
+        _ => None,
+    }
+}
+
+/// Calcula o deslocamento UTC a partir de `timestamp` e `local_timestamp` da
+/// mensagem Activity, arredondado para múltiplos de 15 minutos.
+fn utc_offset_from_activity(record: &FitDataRecord) -> Option<i32> {
+    let timestamp = record_timestamp(record)?;
+    let local_timestamp = record.fields().iter()
+        .find(|f| f.name() == "local_timestamp")
+        .and_then(|f| match f.value() {
+            Value::Timestamp(dt) => Some(*dt),
+            _ => None,
+        })?;
+
+    // O fitparser interpreta local_timestamp como horário de parede no fuso da
+    // máquina a partir da data de referência do FIT; desfazemos essa conversão
+    // usando o deslocamento da máquina naquela data.
+    let reference = NaiveDate::from_ymd_opt(1989, 12, 31)?.and_hms_opt(0, 0, 0)?;
+    let reference_offset = Local.offset_from_local_datetime(&reference).single()?.fix();
+    let local_wall_clock = local_timestamp.naive_utc() + reference_offset;
+
+    let offset = (local_wall_clock - timestamp.naive_utc()).num_seconds();
+    let rounded = ((offset as f64 / 900.0).round() * 900.0) as i32;
+    FixedOffset::east_opt(rounded).map(|o| o.local_minus_utc())
 }
\ No newline at end of file
@@ -0,0 +1,256 @@
+// ============================================================================
+// src/parsers/gpx.rs - Parser para arquivos GPX
+// ============================================================================
+
+use chrono::{DateTime, Utc};
+use log::debug;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{DeError, Reader};
+use std::fs;
+use std::path::PathBuf;
+use crate::analysis::haversine_meters;
+use crate::error::{AppError, AppResult};
+use super::{line_column, ActivityData, Sample};
+
+/// Nome usado quando o GPX não informa o `creator`
+const UNKNOWN_DEVICE: &str = "Dispositivo desconhecido";
+
+/// Elemento de texto sendo lido dentro de `trk`
+#[derive(Debug, Clone, Copy)]
+enum GpxField {
+    /// `trk/type`: esporte (ex: "running")
+    Sport,
+    Elevation,
+    Time,
+    /// `gpxtpx:hr` (ou `ns3:hr`) da extensão TrackPointExtension da Garmin
+    HeartRate,
+    /// `power` das extensões do ponto (exportações do Strava e afins)
+    Power,
+    /// `gpxtpx:cad` da extensão TrackPointExtension da Garmin
+    Cadence,
+}
+
+/// Ponto `trkpt` em leitura; o horário é opcional no GPX
+#[derive(Debug, Default)]
+struct GpxPoint {
+    latitude: f64,
+    longitude: f64,
+    elevation: Option<f64>,
+    time: Option<DateTime<Utc>>,
+    heart_rate: Option<u8>,
+    power: Option<u16>,
+    cadence: Option<u8>,
+}
+
+/// Faz o parsing de um arquivo GPX e retorna os dados da atividade
+pub fn parse_gpx(path: &PathBuf) -> AppResult<ActivityData> {
+    debug!("Lendo arquivo GPX: {:?}", path);
+
+    let gpx_content = fs::read_to_string(path).map_err(AppError::file(path))?;
+    parse_gpx_str(&gpx_content).map_err(|e| e.with_file(path))
+}
+
+/// Faz o parsing do conteúdo XML de um GPX. Lê lat/lon, `ele`, `time`, e FC,
+/// potência e cadência das extensões de cada `trkpt`; pontos sem horário são ignorados.
+/// Os prefixos de namespace são descartados (`gpxtpx:hr`, `ns3:hr`...).
+pub fn parse_gpx_str(gpx_content: &str) -> AppResult<ActivityData> {
+    let gpx_content = gpx_content.trim_start_matches('\u{feff}');
+    let mut reader = Reader::from_str(gpx_content);
+    reader.trim_text(true);
+    reader.check_end_names(true);
+
+    let mut device_name = None;
+    let mut sport = None;
+    let mut samples: Vec<Sample> = Vec::new();
+    let mut in_track = false;
+    let mut point: Option<GpxPoint> = None;
+    let mut field: Option<GpxField> = None;
+    let mut untimed_points = 0usize;
+    let mut total_distance = 0.0;
+    let mut previous_position: Option<(f64, f64)> = None;
+
+    loop {
+        let event = reader.read_event().map_err(|e| xml_error(gpx_content, reader.buffer_position(), e))?;
+        match event {
+            Event::Start(element) => match element.local_name().as_ref() {
+                b"gpx" => device_name = attribute(&element, "creator")?,
+                b"trk" => in_track = true,
+                b"trkpt" => point = Some(start_point(&element)?),
+                b"type" if in_track && point.is_none() => field = Some(GpxField::Sport),
+                b"ele" if point.is_some() => field = Some(GpxField::Elevation),
+                b"time" if point.is_some() => field = Some(GpxField::Time),
+                b"hr" if point.is_some() => field = Some(GpxField::HeartRate),
+                b"power" if point.is_some() => field = Some(GpxField::Power),
+                b"cad" if point.is_some() => field = Some(GpxField::Cadence),
+                _ => field = None,
+            },
+            // `<trkpt lat=".." lon=".."/>` não tem horário
+            Event::Empty(element) if element.local_name().as_ref() == b"trkpt" => untimed_points += 1,
+            Event::Text(text) => {
+                let Some(field) = field else { continue };
+                let text = text.unescape().map_err(|e| xml_error(gpx_content, reader.buffer_position(), e))?;
+                let text = text.trim();
+                match (field, point.as_mut()) {
+                    (GpxField::Sport, _) => sport = Some(text.to_string()),
+                    (GpxField::Elevation, Some(point)) => point.elevation = text.parse().ok(),
+                    (GpxField::Time, Some(point)) => {
+                        point.time = DateTime::parse_from_rfc3339(text).ok().map(|time| time.with_timezone(&Utc));
+                    }
+                    (GpxField::HeartRate, Some(point)) => point.heart_rate = text.parse().ok(),
+                    (GpxField::Power, Some(point)) => point.power = text.parse().ok(),
+                    (GpxField::Cadence, Some(point)) => point.cadence = text.parse().ok(),
+                    _ => {}
+                }
+            }
+            Event::End(element) => {
+                field = None;
+                match element.local_name().as_ref() {
+                    b"trk" => in_track = false,
+                    b"trkpt" => match point.take() {
+                        Some(GpxPoint { time: Some(timestamp), latitude, longitude, elevation, heart_rate, power, cadence }) => {
+                            // GPX não traz distância: acumula a distância entre os pontos
+                            if let Some((previous_lat, previous_lon)) = previous_position {
+                                total_distance += haversine_meters(previous_lat, previous_lon, latitude, longitude);
+                            }
+                            previous_position = Some((latitude, longitude));
+                            samples.push(Sample {
+                                timestamp,
+                                distance_meters: Some(total_distance),
+                                speed_mps: None,
+                                heart_rate,
+                                altitude_meters: elevation,
+                                latitude: Some(latitude),
+                                longitude: Some(longitude),
+                                power_watts: power,
+                                cadence,
+                            });
+                        }
+                        _ => untimed_points += 1,
+                    },
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if untimed_points > 0 {
+        debug!("GPX - {} ponto(s) sem horário ignorado(s)", untimed_points);
+    }
+    let (first, last) = match (samples.first(), samples.last()) {
+        (Some(first), Some(last)) => (first.timestamp, last.timestamp),
+        _ => return Err(AppError::MissingField { format: "GPX", field: "trkpt/time" }),
+    };
+
+    // Sem resumo no GPX: duração e FC vêm dos próprios pontos
+    let heart_rates: Vec<u32> = samples.iter().filter_map(|s| s.heart_rate).map(u32::from).collect();
+    let avg_hr = match heart_rates.len() {
+        0 => 0,
+        count => (heart_rates.iter().sum::<u32>() as f64 / count as f64).round() as u8,
+    };
+    let max_hr = heart_rates.iter().max().map_or(0, |hr| *hr as u8);
+
+    let mut activity_data = ActivityData {
+        total_time_seconds: (last - first).num_milliseconds() as f64 / 1000.0,
+        calories: 0,
+        avg_hr,
+        max_hr,
+        start_time: first,
+        device_name: device_name.unwrap_or_else(|| UNKNOWN_DEVICE.to_string()),
+        sport,
+        utc_offset_seconds: None,
+        moving_time_seconds: None,
+        moving_distance_meters: None,
+        laps: Vec::new(),
+        samples,
+        timer_events: Vec::new(),
+    };
+
+    activity_data.normalize_device_name();
+    debug!("GPX - Nome do dispositivo normalizado: '{}'", activity_data.device_name);
+
+    activity_data.compute_moving_stats();
+
+    Ok(activity_data)
+}
+
+/// Abre um `trkpt`, que exige os atributos `lat` e `lon`
+fn start_point(element: &BytesStart) -> AppResult<GpxPoint> {
+    let coordinate = |name: &'static str| -> AppResult<f64> {
+        attribute(element, name)?
+            .ok_or(AppError::MissingField { format: "GPX", field: name })?
+            .trim()
+            .parse()
+            .map_err(|_| AppError::ParseError(format!("Coordenada '{}' inválida em trkpt", name)))
+    };
+    Ok(GpxPoint { latitude: coordinate("lat")?, longitude: coordinate("lon")?, ..GpxPoint::default() })
+}
+
+/// Valor de um atributo, sem as entidades XML
+fn attribute(element: &BytesStart, name: &str) -> AppResult<Option<String>> {
+    let invalid = |e: quick_xml::Error| AppError::ParseError(format!("Atributo '{}' inválido: {}", name, e));
+    match element.try_get_attribute(name).map_err(invalid)? {
+        Some(attribute) => Ok(Some(attribute.unescape_value().map_err(invalid)?.into_owned())),
+        None => Ok(None),
+    }
+}
+
+fn xml_error(content: &str, offset: usize, source: quick_xml::Error) -> AppError {
+    let (line, column) = line_column(content, offset.min(content.len()));
+    AppError::XmlError { file: None, line: Some(line), column: Some(column), source: DeError::InvalidXml(source) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx creator="Garmin Connect" version="1.1" xmlns="http://www.topografix.com/GPX/1/1"
+     xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1"
+     xmlns:ns3="http://www.garmin.com/xmlschemas/TrackPointExtension/v2">
+  <trk><type>cycling</type><trkseg>
+    <trkpt lat="-23.500" lon="-46.600"><ele>760.0</ele><time>2024-08-20T09:00:00Z</time>
+      <extensions><power>210</power><gpxtpx:TrackPointExtension><gpxtpx:hr>130</gpxtpx:hr><gpxtpx:cad>85</gpxtpx:cad></gpxtpx:TrackPointExtension></extensions>
+    </trkpt>
+    <trkpt lat="-23.501" lon="-46.600"/>
+    <trkpt lat="-23.501" lon="-46.600"><ele>761.0</ele></trkpt>
+    <trkpt lat="-23.501" lon="-46.600"><ele>765.0</ele><time>2024-08-20T09:00:30Z</time>
+      <extensions><power>250</power><ns3:TrackPointExtension><ns3:hr>150</ns3:hr></ns3:TrackPointExtension></extensions>
+    </trkpt>
+  </trkseg></trk>
+</gpx>"#;
+
+    #[test]
+    fn reads_namespaced_extensions() {
+        let activity = parse_gpx_str(GPX).unwrap();
+        assert_eq!(activity.sport.as_deref(), Some("cycling"));
+        let [first, second] = &activity.samples[..] else { panic!("esperava 2 amostras: {:?}", activity.samples) };
+        assert_eq!((first.heart_rate, first.power_watts, first.cadence), (Some(130), Some(210), Some(85)));
+        assert_eq!((second.heart_rate, second.power_watts, second.cadence), (Some(150), Some(250), None));
+        assert_eq!((activity.avg_hr, activity.max_hr), (140, 150));
+        assert_eq!(second.altitude_meters, Some(765.0));
+    }
+
+    #[test]
+    fn untimed_points_are_skipped() {
+        let activity = parse_gpx_str(GPX).unwrap();
+        assert_eq!(activity.samples.len(), 2);
+        assert_eq!(activity.total_time_seconds, 30.0);
+    }
+
+    #[test]
+    fn distance_accumulates_by_haversine() {
+        let activity = parse_gpx_str(GPX).unwrap();
+        let distances: Vec<f64> = activity.samples.iter().filter_map(|s| s.distance_meters).collect();
+        // 0,001° de latitude ≈ 111,2 m
+        assert_eq!(distances[0], 0.0);
+        assert!((distances[1] - 111.19).abs() < 0.05, "{}", distances[1]);
+    }
+
+    #[test]
+    fn without_timed_points_the_time_is_missing() {
+        let gpx = r#"<gpx><trk><trkseg><trkpt lat="0" lon="0"/></trkseg></trk></gpx>"#;
+        assert!(matches!(parse_gpx_str(gpx), Err(AppError::MissingField { format: "GPX", field: "trkpt/time" })));
+    }
+}
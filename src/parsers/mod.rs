@@ -4,11 +4,125 @@
 
 pub mod tcx;
 pub mod fit;
+pub mod gpx;
+pub mod archive;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::Serialize;
+use log::info;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use crate::analysis;
+use crate::constants::{ELEVATION_GAIN_THRESHOLD_METERS, MOVING_SPEED_THRESHOLD_MPS};
+use crate::error::{AppError, AppResult};
+use crate::units::{Locale, Units};
+
+/// Quantos bytes do início do arquivo são inspecionados para achar a raiz do XML
+const SNIFF_XML_BYTES: usize = 4096;
+
+/// Formato de arquivo de atividade
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityFormat {
+    Tcx,
+    Fit,
+    Gpx,
+}
+
+impl ActivityFormat {
+    /// Identifica o formato pelo conteúdo: assinatura ".FIT" no cabeçalho
+    /// ou elemento raiz do XML (`TrainingCenterDatabase` ou `gpx`)
+    pub fn sniff(data: &[u8]) -> Option<Self> {
+        // Cabeçalho FIT: tamanho (12 ou 14) no byte 0 e ".FIT" nos bytes 8..12
+        if matches!(data.first(), Some(12 | 14)) && data.get(8..12) == Some(b".FIT".as_slice()) {
+            return Some(ActivityFormat::Fit);
+        }
+
+        let head = &data[..data.len().min(SNIFF_XML_BYTES)];
+        let head = String::from_utf8_lossy(head);
+        let text = head.trim_start_matches('\u{feff}').trim_start();
+        if text.starts_with('<') && text.contains("<TrainingCenterDatabase") {
+            return Some(ActivityFormat::Tcx);
+        }
+        if text.starts_with('<') && (text.contains("<gpx ") || text.contains("<gpx>")) {
+            return Some(ActivityFormat::Gpx);
+        }
+        None
+    }
+
+    /// Formato pela extensão do arquivo, sem diferenciar maiúsculas
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "tcx" => Some(ActivityFormat::Tcx),
+            "fit" => Some(ActivityFormat::Fit),
+            "gpx" => Some(ActivityFormat::Gpx),
+            _ => None,
+        }
+    }
+}
+
+/// Lê um arquivo de atividade; o formato é identificado pelo conteúdo,
+/// com a extensão (`.tcx`, `.FIT`, `.gpx`...) como alternativa. Exportações
+/// compactadas (`.fit.gz`, `.tcx.gz`, `.zip`) são descompactadas antes.
+pub fn parse_activity_file(path: &PathBuf) -> AppResult<ActivityData> {
+    parse_activity_file_entry(path, None)
+}
+
+/// Como `parse_activity_file`, escolhendo explicitamente a entrada de um
+/// `.zip` (nome completo ou só o do arquivo, ex: "123_ACTIVITY.fit")
+pub fn parse_activity_file_entry(path: &PathBuf, entry: Option<&str>) -> AppResult<ActivityData> {
+    info!("Lendo arquivo de atividade: {:?}", path);
+    let data = fs::read(path).map_err(AppError::file(path))?;
+    let file_name = path.file_name().and_then(|n| n.to_str());
+    let (data, inner_name) = archive::decompress(&data, file_name, entry).map_err(|e| e.with_file(path))?;
+    let format = ActivityFormat::sniff(&data)
+        .or_else(|| ActivityFormat::from_extension(Path::new(inner_name.as_deref().unwrap_or_default())));
+    parse_activity_bytes_as(&data, format).map_err(|e| e.with_file(path))
+}
+
+/// Lê uma atividade de qualquer fonte (stdin, arquivos compactados, uploads)
+pub fn parse_activity_reader<R: Read>(mut reader: R) -> AppResult<ActivityData> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    parse_activity_bytes(&data)
+}
+
+/// Faz o parsing de uma atividade em memória (possivelmente gzip/zip),
+/// identificando o formato pelo conteúdo
+pub fn parse_activity_bytes(data: &[u8]) -> AppResult<ActivityData> {
+    let (data, _) = archive::decompress(data, None, None)?;
+    parse_activity_bytes_as(&data, ActivityFormat::sniff(&data))
+}
+
+fn parse_activity_bytes_as(data: &[u8], format: Option<ActivityFormat>) -> AppResult<ActivityData> {
+    match format {
+        Some(ActivityFormat::Tcx) => {
+            let content = std::str::from_utf8(data)
+                .map_err(|e| AppError::ParseError(format!("TCX não está em UTF-8: {}", e)))?;
+            tcx::parse_tcx_str(content)
+        }
+        Some(ActivityFormat::Fit) => fit::parse_fit_bytes(data),
+        Some(ActivityFormat::Gpx) => {
+            let content = std::str::from_utf8(data)
+                .map_err(|e| AppError::ParseError(format!("GPX não está em UTF-8: {}", e)))?;
+            gpx::parse_gpx_str(content)
+        }
+        None => Err(AppError::InvalidFormat(
+            "Formato não suportado. Use arquivos .tcx, .fit ou .gpx".to_string()
+        )),
+    }
+}
+
+/// Linha e coluna (a partir de 1) de uma posição em bytes no conteúdo XML
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content.as_bytes()[..offset];
+    let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+    let line_start = before.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    (line, offset - line_start + 1)
+}
 
 /// Estrutura unificada para dados de atividade
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ActivityData {
     pub total_time_seconds: f64,
     pub calories: u16,
@@ -16,17 +130,184 @@ pub struct ActivityData {
     pub max_hr: u8,
     pub start_time: DateTime<Utc>,
     pub device_name: String,
+    pub sport: Option<String>,
+    /// Deslocamento UTC (segundos) do horário local gravado pelo dispositivo
+    pub utc_offset_seconds: Option<i32>,
+    pub moving_time_seconds: Option<f64>,
+    pub moving_distance_meters: Option<f64>,
+    pub laps: Vec<Lap>,
+    pub samples: Vec<Sample>,
+    pub timer_events: Vec<TimerEvent>,
+}
+
+/// Volta registrada pelo dispositivo (Lap TCX / mensagem Lap FIT)
+#[derive(Debug, Clone, Serialize)]
+pub struct Lap {
+    pub start_time: DateTime<Utc>,
+    pub total_time_seconds: f64,
+    pub distance_meters: Option<f64>,
+    pub calories: Option<u16>,
+    pub avg_hr: Option<u8>,
+    pub max_hr: Option<u8>,
+}
+
+/// Amostra individual da série temporal da atividade (Trackpoint TCX / Record FIT)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Sample {
+    pub timestamp: DateTime<Utc>,
+    pub distance_meters: Option<f64>,
+    pub speed_mps: Option<f64>,
+    pub heart_rate: Option<u8>,
+    pub altitude_meters: Option<f64>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Potência instantânea (medidor de potência ou estimada pelo dispositivo)
+    pub power_watts: Option<u16>,
+    /// Cadência como o dispositivo registra: rpm no ciclismo, passadas de uma
+    /// perna por minuto na corrida
+    pub cadence: Option<u8>,
+}
+
+/// Evento de início/parada do cronômetro registrado pelo dispositivo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "time", rename_all = "lowercase")]
+pub enum TimerEvent {
+    Start(DateTime<Utc>),
+    Stop(DateTime<Utc>),
 }
 
 impl ActivityData {
+    /// Fim da atividade: última amostra registrada ou início + duração total
+    pub fn end_time(&self) -> DateTime<Utc> {
+        let by_duration = self.start_time + chrono::Duration::milliseconds((self.total_time_seconds * 1000.0) as i64);
+        self.samples.last().map_or(by_duration, |sample| sample.timestamp.max(by_duration))
+    }
+
     /// Formata a duração da atividade em formato legível
     pub fn format_duration(&self) -> String {
-        let total_seconds = self.total_time_seconds as u32;
+        Self::format_seconds(self.total_time_seconds)
+    }
+
+    /// Formata o tempo em movimento, se disponível
+    pub fn format_moving_duration(&self) -> Option<String> {
+        self.moving_time_seconds.map(Self::format_seconds)
+    }
+
+    /// Formata uma quantidade de segundos como "Xh YYm"
+    pub fn format_seconds(seconds: f64) -> String {
+        let total_seconds = seconds as u32;
         let hours = total_seconds / 3600;
         let minutes = (total_seconds % 3600) / 60;
         format!("{}h {:02}m", hours, minutes)
     }
 
+    /// Velocidade média em movimento (m/s)
+    pub fn moving_avg_speed_mps(&self) -> Option<f64> {
+        match (self.moving_distance_meters, self.moving_time_seconds) {
+            (Some(distance), Some(time)) if time > 0.0 && distance > 0.0 => Some(distance / time),
+            _ => None,
+        }
+    }
+
+    /// Nome do esporte para exibição (ex: FIT "trail_running" → "Trail running")
+    pub fn sport_label(&self) -> Option<String> {
+        let sport = self.sport.as_deref()?.trim().replace('_', " ");
+        let mut chars = sport.chars();
+        let first = chars.next()?;
+        Some(first.to_uppercase().chain(chars).collect())
+    }
+
+    /// Distância total: soma das voltas ou, sem elas, a última distância acumulada
+    pub fn total_distance_meters(&self) -> Option<f64> {
+        let lap_distance: Option<f64> = self.laps.iter().map(|lap| lap.distance_meters).sum();
+        lap_distance
+            .filter(|_| !self.laps.is_empty())
+            .or_else(|| self.samples.iter().rev().find_map(|sample| sample.distance_meters))
+    }
+
+    /// Ganho de elevação acumulado (m) pelas altitudes das amostras
+    pub fn elevation_gain_meters(&self) -> Option<f64> {
+        analysis::elevation_gain(&self.samples, ELEVATION_GAIN_THRESHOLD_METERS)
+    }
+
+    /// Potência média das amostras que registram potência
+    pub fn avg_power_watts(&self) -> Option<u16> {
+        let powers: Vec<u32> = self.samples.iter().filter_map(|s| s.power_watts).map(u32::from).collect();
+        match powers.len() {
+            0 => None,
+            count => Some((powers.iter().sum::<u32>() as f64 / count as f64).round() as u16),
+        }
+    }
+
+    /// Potência máxima registrada nas amostras
+    pub fn max_power_watts(&self) -> Option<u16> {
+        self.samples.iter().filter_map(|s| s.power_watts).max()
+    }
+
+    /// Cadência média das amostras com cadência, sem os zeros (pedal parado)
+    pub fn avg_cadence(&self) -> Option<u8> {
+        let cadences: Vec<u32> = self.samples.iter().filter_map(|s| s.cadence).filter(|&c| c > 0).map(u32::from).collect();
+        match cadences.len() {
+            0 => None,
+            count => Some((cadences.iter().sum::<u32>() as f64 / count as f64).round() as u8),
+        }
+    }
+
+    /// Formata a cadência média: passos por minuto (o dobro da cadência de uma
+    /// perna) nos esportes medidos em ritmo, rpm nos demais
+    pub fn format_avg_cadence(&self) -> Option<String> {
+        let cadence = self.avg_cadence()?;
+        Some(if self.uses_pace() {
+            format!("{} spm", u16::from(cadence) * 2)
+        } else {
+            format!("{} rpm", cadence)
+        })
+    }
+
+    /// Indica se o esporte é normalmente medido em ritmo (min/km) em vez de velocidade
+    pub fn uses_pace(&self) -> bool {
+        let sport = self.sport.as_deref().unwrap_or("").to_lowercase();
+        sport.contains("run") || sport.contains("walk") || sport.contains("hik")
+    }
+
+    /// Formata o ritmo/velocidade média em movimento de acordo com o esporte
+    pub fn format_moving_pace_or_speed(&self, units: Units) -> Option<String> {
+        let speed = self.moving_avg_speed_mps()?;
+        Some(units.format_pace_or_speed(speed, self.uses_pace()))
+    }
+
+    /// Resumo textual da atividade (usado em EXIF ImageDescription / XMP)
+    pub fn summary_text(&self, start_time_local: DateTime<FixedOffset>, units: Units, locale: Locale) -> String {
+        let mut parts = Vec::new();
+        if let Some(sport) = &self.sport {
+            parts.push(sport.clone());
+        }
+        parts.push(format!("{} {}", locale.format_date(start_time_local), locale.format_time(start_time_local)));
+        parts.push(self.format_duration());
+        if let Some(moving) = self.format_moving_duration() {
+            parts.push(format!("{} mov", moving));
+        }
+        if let Some(pace_or_speed) = self.format_moving_pace_or_speed(units) {
+            parts.push(pace_or_speed);
+        }
+        parts.push(format!("{} kcal", self.calories));
+        parts.push(format!("FC {}/{} bpm", self.avg_hr, self.max_hr));
+        parts.push(self.device_name.clone());
+        parts.join(" | ")
+    }
+
+    /// Calcula tempo e distância em movimento a partir da série de amostras
+    pub fn compute_moving_stats(&mut self) {
+        if let Some(stats) = analysis::compute_moving_stats(
+            &self.samples,
+            &self.timer_events,
+            MOVING_SPEED_THRESHOLD_MPS,
+        ) {
+            self.moving_time_seconds = Some(stats.moving_time_seconds);
+            self.moving_distance_meters = Some(stats.moving_distance_meters);
+        }
+    }
+
     /// Normaliza o nome do dispositivo para formato consistente "Marca Modelo"
     pub fn normalize_device_name(&mut self) {
         self.device_name = Self::normalize_device_name_static(&self.device_name);
@@ -4,10 +4,16 @@
 
 use serde::Deserialize;
 use chrono::{DateTime, Utc};
+use log::debug;
+use quick_xml::events::Event;
+use quick_xml::Reader;
 use std::fs;
 use std::path::PathBuf;
 use crate::error::{AppResult, AppError};
-use super::ActivityData;
+use super::{line_column, ActivityData, Lap, Sample};
+
+/// Valores de `Sport` definidos pelo esquema TCX (`Sport_t`)
+const TCX_SPORTS: &[&str] = &["Running", "Biking", "Other"];
 
 #[derive(Debug, Deserialize)]
 #[serde(rename = "TrainingCenterDatabase")]
@@ -24,8 +30,10 @@ struct TcxActivities {
 
 #[derive(Debug, Deserialize)]
 struct TcxActivity {
+    #[serde(rename = "@Sport")]
+    sport: Option<String>,
     #[serde(rename = "Lap")]
-    lap: TcxLap,
+    laps: Vec<TcxLap>,
     #[serde(rename = "Creator")]
     creator: TcxCreator,
 }
@@ -34,6 +42,8 @@ struct TcxActivity {
 struct TcxLap {
     #[serde(rename = "TotalTimeSeconds")]
     total_time_seconds: f64,
+    #[serde(rename = "DistanceMeters")]
+    distance_meters: Option<f64>,
     #[serde(rename = "Calories")]
     calories: u16,
     #[serde(rename = "AverageHeartRateBpm")]
@@ -42,6 +52,8 @@ struct TcxLap {
     max_hr: TcxHeartRate,
     #[serde(rename = "@StartTime")]
     start_time: DateTime<Utc>,
+    #[serde(rename = "Track", default)]
+    tracks: Vec<TcxTrack>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -50,6 +62,55 @@ struct TcxHeartRate {
     value: u8,
 }
 
+#[derive(Debug, Deserialize)]
+struct TcxTrack {
+    #[serde(rename = "Trackpoint", default)]
+    trackpoints: Vec<TcxTrackpoint>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TcxTrackpoint {
+    #[serde(rename = "Time")]
+    time: DateTime<Utc>,
+    #[serde(rename = "Position")]
+    position: Option<TcxPosition>,
+    #[serde(rename = "AltitudeMeters")]
+    altitude_meters: Option<f64>,
+    #[serde(rename = "DistanceMeters")]
+    distance_meters: Option<f64>,
+    #[serde(rename = "HeartRateBpm")]
+    heart_rate: Option<TcxHeartRate>,
+    /// Cadência do ciclismo (rpm)
+    #[serde(rename = "Cadence")]
+    cadence: Option<u8>,
+    #[serde(rename = "Extensions")]
+    extensions: Option<TcxTrackpointExtensions>,
+}
+
+/// `Extensions/ns3:TPX` do Trackpoint (ActivityExtension v2 da Garmin)
+#[derive(Debug, Deserialize)]
+struct TcxTrackpointExtensions {
+    #[serde(rename = "TPX")]
+    tpx: Option<TcxTpx>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TcxTpx {
+    #[serde(rename = "Watts")]
+    watts: Option<u16>,
+    /// Cadência da corrida (passadas de uma perna por minuto)
+    #[serde(rename = "RunCadence")]
+    run_cadence: Option<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TcxPosition {
+    #[serde(rename = "LatitudeDegrees")]
+    latitude_degrees: f64,
+    #[serde(rename = "LongitudeDegrees")]
+    longitude_degrees: f64,
+}
+
 #[derive(Debug, Deserialize)]
 struct TcxCreator {
     #[serde(rename = "Name")]
@@ -58,27 +119,117 @@ struct TcxCreator {
 
 /// Faz o parsing de um arquivo TCX e retorna os dados da atividade
 pub fn parse_tcx(path: &PathBuf) -> AppResult<ActivityData> {
-    println!("Lendo arquivo TCX: {:?}", path);
+    debug!("Lendo arquivo TCX: {:?}", path);
     
-    let tcx_content = fs::read_to_string(path)?;
-    let tcx_data: TcxDatabase = quick_xml::de::from_str(&tcx_content)
-        .map_err(|e| AppError::ParseError(format!("Erro ao parsear TCX: {}", e)))?;
+    let tcx_content = fs::read_to_string(path).map_err(AppError::file(path))?;
+    parse_tcx_str(&tcx_content).map_err(|e| e.with_file(path))
+}
+
+/// Faz o parsing do conteúdo XML de um TCX (stdin, arquivos compactados, uploads)
+pub fn parse_tcx_str(tcx_content: &str) -> AppResult<ActivityData> {
+    let tcx_content = tcx_content.trim_start_matches('\u{feff}');
+    let tcx_data: TcxDatabase = quick_xml::de::from_str(tcx_content).map_err(|source| {
+        let position = syntax_error_position(tcx_content);
+        AppError::XmlError {
+            file: None,
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+            source,
+        }
+    })?;
+
+    let activity = &tcx_data.activities.activity;
+    if let Some(sport) = activity.sport.as_deref().filter(|sport| !TCX_SPORTS.contains(sport)) {
+        return Err(AppError::UnsupportedSport { format: "TCX", sport: sport.to_string() });
+    }
+    let laps = &activity.laps;
+    let first_lap = laps.first()
+        .ok_or(AppError::MissingField { format: "TCX", field: "Lap" })?;
+
+    // Agrega as voltas: tempos e calorias somados, FC média ponderada pelo tempo
+    let total_time_seconds: f64 = laps.iter().map(|lap| lap.total_time_seconds).sum();
+    let calories = laps.iter().map(|lap| lap.calories as u32).sum::<u32>().min(u16::MAX as u32) as u16;
+    let max_hr = laps.iter().map(|lap| lap.max_hr.value).max().unwrap_or(0);
+    let avg_hr = if total_time_seconds > 0.0 {
+        let weighted: f64 = laps.iter().map(|lap| lap.avg_hr.value as f64 * lap.total_time_seconds).sum();
+        (weighted / total_time_seconds).round() as u8
+    } else {
+        first_lap.avg_hr.value
+    };
 
-    let lap = &tcx_data.activities.activity.lap;
-    let creator = &tcx_data.activities.activity.creator;
+    let samples: Vec<Sample> = laps.iter()
+        .flat_map(|lap| lap.tracks.iter())
+        .flat_map(|track| track.trackpoints.iter())
+        .map(|point| Sample {
+            timestamp: point.time,
+            distance_meters: point.distance_meters,
+            speed_mps: None,
+            heart_rate: point.heart_rate.as_ref().map(|hr| hr.value),
+            altitude_meters: point.altitude_meters,
+            latitude: point.position.as_ref().map(|p| p.latitude_degrees),
+            longitude: point.position.as_ref().map(|p| p.longitude_degrees),
+            power_watts: point.extensions.as_ref().and_then(|e| e.tpx.as_ref()).and_then(|tpx| tpx.watts),
+            cadence: point.cadence.or_else(|| {
+                point.extensions.as_ref().and_then(|e| e.tpx.as_ref()).and_then(|tpx| tpx.run_cadence)
+            }),
+        })
+        .collect();
+
+    let lap_summaries = laps.iter()
+        .map(|lap| Lap {
+            start_time: lap.start_time,
+            total_time_seconds: lap.total_time_seconds,
+            distance_meters: lap.distance_meters,
+            calories: Some(lap.calories),
+            avg_hr: Some(lap.avg_hr.value),
+            max_hr: Some(lap.max_hr.value),
+        })
+        .collect();
 
     let mut activity_data = ActivityData {
-        total_time_seconds: lap.total_time_seconds,
-        calories: lap.calories,
-        avg_hr: lap.avg_hr.value,
-        max_hr: lap.max_hr.value,
-        start_time: lap.start_time,
-        device_name: creator.name.clone(),
+        total_time_seconds,
+        calories,
+        avg_hr,
+        max_hr,
+        start_time: first_lap.start_time,
+        device_name: activity.creator.name.clone(),
+        sport: activity.sport.clone(),
+        utc_offset_seconds: None,
+        moving_time_seconds: None,
+        moving_distance_meters: None,
+        laps: lap_summaries,
+        samples,
+        timer_events: Vec::new(),
     };
 
     // Normaliza o nome do dispositivo
     activity_data.normalize_device_name();
-    println!("TCX - Nome do dispositivo normalizado: '{}'", activity_data.device_name);
+    debug!("TCX - Nome do dispositivo normalizado: '{}'", activity_data.device_name);
+
+    activity_data.compute_moving_stats();
 
     Ok(activity_data)
-}
\ No newline at end of file
+}
+
+/// Linha e coluna (a partir de 1) do primeiro erro de sintaxe do XML ou,
+/// em arquivos truncados, do fim do conteúdo. O desserializador não informa
+/// a posição; uma segunda leitura só com o leitor de eventos a localiza.
+/// `None` quando o XML é bem formado e o erro vem do esquema (ex: elemento
+/// obrigatório ausente).
+fn syntax_error_position(content: &str) -> Option<(usize, usize)> {
+    let mut reader = Reader::from_str(content);
+    reader.check_end_names(true);
+    let mut depth = 0usize;
+    let offset = loop {
+        match reader.read_event() {
+            Ok(Event::Eof) if depth == 0 => return None,
+            Ok(Event::Eof) => break content.len(),
+            Ok(Event::Start(_)) => depth += 1,
+            Ok(Event::End(_)) => depth = depth.saturating_sub(1),
+            Ok(_) => {}
+            Err(_) => break reader.buffer_position().min(content.len()),
+        }
+    };
+
+    Some(line_column(content, offset))
+}
@@ -0,0 +1,273 @@
+// ============================================================================
+// src/photo_metadata.rs - Leitura de metadados EXIF da foto
+// ============================================================================
+
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use exif::{Context, Exif, Field, In, Tag, Value};
+use image::DynamicImage;
+use img_parts::jpeg::{markers, JpegSegment};
+use img_parts::png::PngChunk;
+use img_parts::{Bytes, DynImage, ImageEXIF, ImageICC};
+use log::{debug, warn};
+use std::io::Cursor;
+
+use crate::error::{AppError, AppResult};
+
+/// Cabeçalho do segmento APP1 que carrega XMP em arquivos JPEG
+const XMP_JPEG_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// Palavra-chave do chunk iTXt que carrega XMP em arquivos PNG
+const XMP_PNG_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+/// Metadados da foto de entrada que podem ser levados para a imagem final
+#[derive(Debug, Clone, Default)]
+pub struct SourceMetadata {
+    pub exif_fields: Vec<Field>,
+    pub exif_little_endian: bool,
+    pub icc_profile: Option<Bytes>,
+}
+
+/// Opções de metadados aplicadas ao salvar a imagem final
+#[derive(Debug, Clone, Default)]
+pub struct MetadataOptions {
+    /// Copia EXIF (data de captura, câmera) e perfil ICC da foto original
+    pub keep_metadata: bool,
+    /// Remove as coordenadas GPS do EXIF copiado
+    pub strip_gps: bool,
+    /// Resumo da atividade gravado em EXIF ImageDescription e XMP dc:description
+    pub description: Option<String>,
+}
+
+impl MetadataOptions {
+    /// Indica se algum metadado precisa ser gravado na saída
+    pub fn is_active(&self) -> bool {
+        self.keep_metadata || self.description.is_some()
+    }
+}
+
+/// Momento de captura da foto gravado pela câmera
+#[derive(Debug, Clone, Copy)]
+pub struct PhotoCaptureTime {
+    /// Horário de parede da câmera (EXIF DateTimeOriginal)
+    pub local: NaiveDateTime,
+    /// Deslocamento UTC (EXIF OffsetTimeOriginal), quando gravado
+    pub offset: Option<FixedOffset>,
+}
+
+impl PhotoCaptureTime {
+    /// Converte para UTC, usando `fallback_offset` quando o EXIF não tem deslocamento
+    pub fn to_utc(self, fallback_offset: FixedOffset) -> Option<DateTime<Utc>> {
+        self.offset
+            .unwrap_or(fallback_offset)
+            .from_local_datetime(&self.local)
+            .single()
+            .map(|dt| dt.with_timezone(&Utc))
+    }
+}
+
+/// Lê o bloco EXIF da foto; retorna `None` se o arquivo não tiver EXIF
+pub fn read_exif(image_bytes: &[u8]) -> Option<Exif> {
+    exif::Reader::new()
+        .read_from_container(&mut Cursor::new(image_bytes))
+        .ok()
+}
+
+/// Coleta os metadados da foto de entrada (EXIF já lido e perfil ICC)
+pub fn read_source_metadata(image_bytes: &[u8], exif: Option<&Exif>) -> SourceMetadata {
+    let icc_profile = DynImage::from_bytes(Bytes::copy_from_slice(image_bytes))
+        .ok()
+        .flatten()
+        .and_then(|image| image.icc_profile());
+
+    SourceMetadata {
+        exif_fields: exif.map(|e| e.fields().cloned().collect()).unwrap_or_default(),
+        exif_little_endian: exif.is_some_and(|e| e.little_endian()),
+        icc_profile,
+    }
+}
+
+/// Momento de captura lido direto dos bytes da foto, sem decodificar a imagem
+pub fn photo_capture_time(image_bytes: &[u8]) -> Option<PhotoCaptureTime> {
+    read_exif(image_bytes).as_ref().and_then(read_capture_time)
+}
+
+/// Extrai o momento de captura (DateTimeOriginal, com fallback para DateTime)
+pub fn read_capture_time(exif: &Exif) -> Option<PhotoCaptureTime> {
+    [
+        (Tag::DateTimeOriginal, Tag::OffsetTimeOriginal),
+        (Tag::DateTime, Tag::OffsetTime),
+    ]
+    .iter()
+    .find_map(|&(datetime_tag, offset_tag)| {
+        let mut datetime = ascii_field(exif, datetime_tag)
+            .and_then(|data| exif::DateTime::from_ascii(data).ok())?;
+        if let Some(offset_data) = ascii_field(exif, offset_tag) {
+            let _ = datetime.parse_offset(offset_data);
+        }
+
+        let local = NaiveDate::from_ymd_opt(datetime.year as i32, datetime.month as u32, datetime.day as u32)?
+            .and_hms_opt(datetime.hour as u32, datetime.minute as u32, datetime.second as u32)?;
+        let offset = datetime.offset.and_then(|minutes| FixedOffset::east_opt(minutes as i32 * 60));
+
+        Some(PhotoCaptureTime { local, offset })
+    })
+}
+
+/// Retorna o conteúdo ASCII de um campo EXIF do IFD principal
+fn ascii_field(exif: &Exif, tag: Tag) -> Option<&[u8]> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => values.first().map(|v| v.as_slice()),
+        _ => None,
+    }
+}
+
+/// Lê a tag EXIF Orientation (1-8); retorna 1 (normal) quando ausente
+pub fn read_orientation(exif: &Exif) -> u32 {
+    exif.get_field(Tag::Orientation, In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+        .filter(|orientation| (1..=8).contains(orientation))
+        .unwrap_or(1)
+}
+
+/// Aplica a transformação indicada pela tag Orientation para que a imagem
+/// fique como é exibida na galeria do celular
+pub fn apply_orientation(image: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+/// Grava EXIF, ICC e XMP na imagem já codificada (JPEG, PNG ou WebP).
+/// Formatos sem suporte a metadados são devolvidos sem alteração.
+pub fn embed_metadata(encoded: Vec<u8>, source: &SourceMetadata, options: &MetadataOptions) -> AppResult<Vec<u8>> {
+    if !options.is_active() {
+        return Ok(encoded);
+    }
+
+    let Some(mut image) = DynImage::from_bytes(Bytes::from(encoded.clone()))
+        .map_err(|e| AppError::InvalidFormat(format!("Falha ao ler imagem codificada: {}", e)))?
+    else {
+        warn!("⚠️  Formato de saída não suporta metadados; salvando sem EXIF/ICC/XMP");
+        return Ok(encoded);
+    };
+
+    let exif = build_exif(source, options)?;
+    image.set_exif(exif.map(Bytes::from));
+    if options.keep_metadata {
+        image.set_icc_profile(source.icc_profile.clone());
+    }
+
+    if let Some(description) = &options.description {
+        let xmp = build_xmp_packet(description);
+        match &mut image {
+            DynImage::Jpeg(jpeg) => {
+                let mut contents = XMP_JPEG_HEADER.to_vec();
+                contents.extend_from_slice(xmp.as_bytes());
+                let segment = JpegSegment::new_with_contents(markers::APP1, Bytes::from(contents));
+                // Logo após o APP1 do EXIF (ou no início, se não houver)
+                let position = jpeg.segments().iter()
+                    .position(|segment| segment.marker() == markers::APP1)
+                    .map_or(0, |index| index + 1);
+                jpeg.segments_mut().insert(position, segment);
+            }
+            DynImage::Png(png) => {
+                let mut contents = XMP_PNG_KEYWORD.to_vec();
+                // Separador, sem compressão, método 0, idioma e palavra traduzida vazios
+                contents.extend_from_slice(&[0, 0, 0, 0, 0]);
+                contents.extend_from_slice(xmp.as_bytes());
+                let chunk = PngChunk::new(*b"iTXt", Bytes::from(contents));
+                let position = png.chunks().len().saturating_sub(1);
+                png.chunks_mut().insert(position, chunk);
+            }
+            _ => warn!("⚠️  XMP não suportado neste formato; resumo gravado apenas no EXIF"),
+        }
+    }
+
+    let mut output = Vec::new();
+    image.encoder().write_to(&mut output)?;
+    Ok(output)
+}
+
+/// Reconstrói o bloco EXIF (TIFF) a partir dos campos da foto original
+fn build_exif(source: &SourceMetadata, options: &MetadataOptions) -> AppResult<Option<Vec<u8>>> {
+    let mut fields: Vec<&Field> = Vec::new();
+    if options.keep_metadata {
+        for field in source.exif_fields.iter().filter(|field| should_keep_field(field, options)) {
+            // O gravador não aceita campos repetidos; vale a primeira ocorrência
+            if !fields.iter().any(|kept| kept.tag == field.tag) {
+                fields.push(field);
+            }
+        }
+    }
+
+    let description_field = options.description.as_ref().map(|description| Field {
+        tag: Tag::ImageDescription,
+        ifd_num: In::PRIMARY,
+        value: Value::Ascii(vec![description.as_bytes().to_vec()]),
+    });
+    fields.extend(description_field.as_ref());
+
+    if fields.is_empty() {
+        return Ok(None);
+    }
+
+    let mut writer = exif::experimental::Writer::new();
+    for field in fields {
+        writer.push_field(field);
+    }
+    let mut buffer = Cursor::new(Vec::new());
+    writer.write(&mut buffer, source.exif_little_endian)
+        .map_err(|e| AppError::InvalidFormat(format!("Falha ao gravar EXIF: {}", e)))?;
+    Ok(Some(buffer.into_inner()))
+}
+
+/// Decide se um campo EXIF da foto original é copiado para a saída
+fn should_keep_field(field: &Field, options: &MetadataOptions) -> bool {
+    // Miniatura, orientação e dimensões não correspondem mais à imagem final;
+    // MakerNote depende de offsets do arquivo original
+    if field.ifd_num != In::PRIMARY {
+        return false;
+    }
+    // Tags de fabricante com tipo desconhecido não podem ser regravadas
+    if let Value::Unknown(type_id, _, _) = field.value {
+        debug!("Campo EXIF {} ignorado: tipo {} não suportado na gravação", field.tag, type_id);
+        return false;
+    }
+    if matches!(
+        field.tag,
+        Tag::Orientation | Tag::MakerNote | Tag::PixelXDimension | Tag::PixelYDimension
+            | Tag::ImageWidth | Tag::ImageLength
+    ) {
+        return false;
+    }
+    if options.strip_gps && field.tag.context() == Context::Gps {
+        return false;
+    }
+    !(options.description.is_some() && field.tag == Tag::ImageDescription)
+}
+
+/// Monta um pacote XMP mínimo com o resumo em dc:description
+fn build_xmp_packet(description: &str) -> String {
+    let escaped = description
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!(
+        concat!(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>",
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">",
+            "<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">",
+            "<rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">",
+            "<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>",
+            "</rdf:Description></rdf:RDF></x:xmpmeta>",
+            "<?xpacket end=\"w\"?>"
+        ),
+        escaped
+    )
+}
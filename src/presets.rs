@@ -0,0 +1,195 @@
+// ============================================================================
+// src/presets.rs - Presets de redes sociais (recorte, letterbox e redimensionamento)
+// ============================================================================
+
+use clap::ValueEnum;
+use image::{imageops, Rgba, RgbaImage};
+use log::debug;
+use std::str::FromStr;
+
+/// Raio do desfoque usado no preenchimento do letterbox
+const LETTERBOX_BLUR_SIGMA: f32 = 12.0;
+/// Lado máximo da imagem reduzida usada na análise de detalhes do recorte inteligente
+const SMART_CROP_ANALYSIS_SIZE: u32 = 256;
+
+/// Preset de saída para redes sociais
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputPreset {
+    /// Instagram 1:1 (1080×1080)
+    Square,
+    /// Instagram 4:5 (1080×1350)
+    Portrait,
+    /// Stories 9:16 (1080×1920)
+    Story,
+    /// Strava 16:9 (1920×1080)
+    Strava,
+}
+
+impl OutputPreset {
+    /// Dimensões finais do preset
+    pub fn dimensions(self) -> (u32, u32) {
+        match self {
+            OutputPreset::Square => (1080, 1080),
+            OutputPreset::Portrait => (1080, 1350),
+            OutputPreset::Story => (1080, 1920),
+            OutputPreset::Strava => (1920, 1080),
+        }
+    }
+}
+
+/// Como a foto é encaixada na proporção do preset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum FitMode {
+    /// Recorta o excesso da foto
+    #[default]
+    Crop,
+    /// Mantém a foto inteira e preenche as bordas (letterbox)
+    Pad,
+}
+
+/// Região da foto preservada no recorte
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum CropFocus {
+    #[default]
+    Center,
+    /// Região com mais detalhes (maior energia de bordas)
+    Smart,
+}
+
+/// Preenchimento das bordas no modo letterbox
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LetterboxFill {
+    /// A própria foto ampliada e desfocada
+    #[default]
+    Blur,
+    /// Cor sólida
+    Color(Rgba<u8>),
+}
+
+impl FromStr for LetterboxFill {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.eq_ignore_ascii_case("blur") {
+            return Ok(LetterboxFill::Blur);
+        }
+
+        let hex = value.trim_start_matches("color:").trim_start_matches('#');
+        let channel = |range: std::ops::Range<usize>| hex.get(range).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (hex.len(), channel(0..2), channel(2..4), channel(4..6)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(LetterboxFill::Color(Rgba([r, g, b, 255]))),
+            _ => Err(format!("Preenchimento inválido: '{}'. Use 'blur' ou uma cor como '#202020'", value)),
+        }
+    }
+}
+
+/// Opções completas de preset
+#[derive(Debug, Clone, Copy)]
+pub struct PresetOptions {
+    pub preset: OutputPreset,
+    pub fit: FitMode,
+    pub focus: CropFocus,
+    pub fill: LetterboxFill,
+}
+
+/// Aplica o preset: recorta ou adiciona letterbox e redimensiona para o tamanho final
+pub fn apply_preset(image: &RgbaImage, options: &PresetOptions) -> RgbaImage {
+    let (target_width, target_height) = options.preset.dimensions();
+    debug!("📐 Aplicando preset {:?} ({}x{}, {:?})", options.preset, target_width, target_height, options.fit);
+
+    match options.fit {
+        FitMode::Crop => crop_to_fill(image, target_width, target_height, options.focus),
+        FitMode::Pad => pad_to_fit(image, target_width, target_height, options.fill),
+    }
+}
+
+/// Amplia a foto para cobrir o quadro e recorta o excesso
+fn crop_to_fill(image: &RgbaImage, target_width: u32, target_height: u32, focus: CropFocus) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let scale = (target_width as f32 / width as f32).max(target_height as f32 / height as f32);
+
+    // Janela de recorte nas coordenadas da foto original
+    let crop_width = ((target_width as f32 / scale).round() as u32).clamp(1, width);
+    let crop_height = ((target_height as f32 / scale).round() as u32).clamp(1, height);
+
+    let (crop_x, crop_y) = match focus {
+        CropFocus::Center => ((width - crop_width) / 2, (height - crop_height) / 2),
+        CropFocus::Smart => smart_crop_origin(image, crop_width, crop_height),
+    };
+    debug!("📐 Recorte: {}x{} em ({}, {})", crop_width, crop_height, crop_x, crop_y);
+
+    let cropped = imageops::crop_imm(image, crop_x, crop_y, crop_width, crop_height).to_image();
+    imageops::resize(&cropped, target_width, target_height, imageops::FilterType::Lanczos3)
+}
+
+/// Reduz a foto para caber no quadro e preenche as bordas
+fn pad_to_fit(image: &RgbaImage, target_width: u32, target_height: u32, fill: LetterboxFill) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let scale = (target_width as f32 / width as f32).min(target_height as f32 / height as f32);
+    let fitted_width = ((width as f32 * scale).round() as u32).clamp(1, target_width);
+    let fitted_height = ((height as f32 * scale).round() as u32).clamp(1, target_height);
+
+    let mut canvas = match fill {
+        LetterboxFill::Color(color) => RgbaImage::from_pixel(target_width, target_height, color),
+        LetterboxFill::Blur => {
+            // Desfoca uma versão reduzida (mais rápido) e amplia para o quadro
+            let background = crop_to_fill(image, target_width / 4, target_height / 4, CropFocus::Center);
+            let blurred = imageops::blur(&background, LETTERBOX_BLUR_SIGMA / 4.0);
+            imageops::resize(&blurred, target_width, target_height, imageops::FilterType::Triangle)
+        }
+    };
+
+    let fitted = imageops::resize(image, fitted_width, fitted_height, imageops::FilterType::Lanczos3);
+    let offset_x = (target_width - fitted_width) / 2;
+    let offset_y = (target_height - fitted_height) / 2;
+    imageops::overlay(&mut canvas, &fitted, offset_x as i64, offset_y as i64);
+    canvas
+}
+
+/// Escolhe a origem do recorte que maximiza a quantidade de detalhes.
+/// O recorte só desliza em um eixo (o outro já ocupa a foto inteira), então
+/// basta somar a energia de bordas por coluna ou por linha.
+fn smart_crop_origin(image: &RgbaImage, crop_width: u32, crop_height: u32) -> (u32, u32) {
+    let (width, height) = image.dimensions();
+    let factor = (width.max(height) as f32 / SMART_CROP_ANALYSIS_SIZE as f32).max(1.0);
+    let small_width = ((width as f32 / factor) as u32).max(2);
+    let small_height = ((height as f32 / factor) as u32).max(2);
+    let small = imageops::resize(image, small_width, small_height, imageops::FilterType::Triangle);
+
+    let luma = |x: u32, y: u32| {
+        let p = small.get_pixel(x, y);
+        0.2126 * p[0] as f32 + 0.7152 * p[1] as f32 + 0.0722 * p[2] as f32
+    };
+
+    let horizontal = crop_width < width;
+    let profile_len = if horizontal { small_width } else { small_height };
+    let mut profile = vec![0.0f32; profile_len as usize];
+    for y in 0..small_height - 1 {
+        for x in 0..small_width - 1 {
+            let energy = (luma(x + 1, y) - luma(x, y)).abs() + (luma(x, y + 1) - luma(x, y)).abs();
+            profile[if horizontal { x } else { y } as usize] += energy;
+        }
+    }
+
+    let (full, crop) = if horizontal { (width, crop_width) } else { (height, crop_height) };
+    let window = ((crop as f32 / factor).round() as usize).clamp(1, profile.len());
+    let mut best_start = (profile.len() - window) / 2;
+    let mut best_energy = f32::MIN;
+    let mut energy: f32 = profile[..window].iter().sum();
+    for start in 0..=profile.len() - window {
+        if start > 0 {
+            energy += profile[start + window - 1] - profile[start - 1];
+        }
+        if energy > best_energy {
+            best_energy = energy;
+            best_start = start;
+        }
+    }
+
+    let origin = ((best_start as f32 * factor).round() as u32).min(full - crop);
+    if horizontal {
+        (origin, (height - crop_height) / 2)
+    } else {
+        ((width - crop_width) / 2, origin)
+    }
+}
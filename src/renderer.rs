@@ -0,0 +1,202 @@
+// ============================================================================
+// src/renderer.rs - Renderizadores do overlay (raster e SVG vetorial)
+// ============================================================================
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use clap::ValueEnum;
+use image::{imageops, Rgba, RgbaImage};
+use imageproc::drawing::draw_text_mut;
+use rusttype::{Font, Scale};
+use std::fmt::Write;
+
+use crate::assets::{AssetSource, FontAsset};
+use crate::encoder::{self, EncoderOptions, OutputFormat};
+use crate::error::AppResult;
+use crate::fonts::FontSet;
+
+/// Fonte usada em uma chamada de desenho de texto
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontRole {
+    /// Rótulos, unidades e textos (ex: "kcal", nome do dispositivo)
+    Label,
+    /// Valores numéricos (ex: "1:23:45", "523")
+    Number,
+    /// FontAwesome embutido
+    Icon,
+    /// Fonte do conjunto de ícones (`--icons`)
+    IconSet,
+}
+
+/// Destino das primitivas do overlay. O layout é calculado uma única vez
+/// (com as métricas do rusttype) e desenhado pelo renderizador escolhido.
+pub trait OverlayRenderer {
+    /// Desenha um texto com o topo da caixa em `(x, y)`, como `draw_text_mut`
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, scale: Scale, role: FontRole, color: Rgba<u8>);
+
+    /// Sobrepõe uma imagem (ex: marca d'água) com o canto superior esquerdo em `(x, y)`
+    fn draw_image(&mut self, image: &RgbaImage, x: i64, y: i64) -> AppResult<()>;
+}
+
+/// Desenha direto nos pixels da imagem
+pub struct RasterRenderer<'a> {
+    image: &'a mut RgbaImage,
+    fonts: &'a FontSet,
+}
+
+impl<'a> RasterRenderer<'a> {
+    pub fn new(image: &'a mut RgbaImage, fonts: &'a FontSet) -> Self {
+        Self { image, fonts }
+    }
+}
+
+impl OverlayRenderer for RasterRenderer<'_> {
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, scale: Scale, role: FontRole, color: Rgba<u8>) {
+        // Cada trecho usa a fonte da pilha que tem seus glifos
+        for run in self.fonts.stack(role).layout(scale, text) {
+            draw_text_mut(self.image, color, x + run.x, y + run.y, scale, run.font, run.text);
+        }
+    }
+
+    fn draw_image(&mut self, image: &RgbaImage, x: i64, y: i64) -> AppResult<()> {
+        imageops::overlay(self.image, image, x, y);
+        Ok(())
+    }
+}
+
+/// Como as fontes são referenciadas no documento SVG
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SvgFontMode {
+    /// Embute os arquivos TTF em base64 (documento autocontido)
+    #[default]
+    Embed,
+    /// Referencia as fontes pelo nome e pelo caminho local do arquivo
+    /// (só pelo nome quando a fonte está embutida no binário)
+    Reference,
+}
+
+/// Gera um documento SVG com texto e ícones editáveis em ferramentas vetoriais
+pub struct SvgRenderer<'a> {
+    width: u32,
+    height: u32,
+    fonts: &'a FontSet,
+    font_mode: SvgFontMode,
+    elements: Vec<String>,
+}
+
+impl<'a> SvgRenderer<'a> {
+    pub fn new(width: u32, height: u32, fonts: &'a FontSet, font_mode: SvgFontMode) -> Self {
+        Self { width, height, fonts, font_mode, elements: Vec::new() }
+    }
+
+    /// Embute a foto de fundo (JPEG quando opaca, PNG quando tem transparência)
+    pub fn draw_background(&mut self, image: &RgbaImage, quality: u8) -> AppResult<()> {
+        let format = if image.pixels().all(|p| p[3] == 255) { OutputFormat::Jpeg } else { OutputFormat::Png };
+        let options = EncoderOptions { quality, ..EncoderOptions::default() };
+        let encoded = encoder::encode(image, format, &options)?;
+        self.push_image(&encoded, format.mime_type(), 0, 0, image.width(), image.height());
+        Ok(())
+    }
+
+    /// Finaliza o documento, com as fontes em `<style>` e os elementos na ordem de desenho
+    pub fn finish(self) -> AppResult<String> {
+        let mut document = String::new();
+        let _ = writeln!(document, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            document,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = self.width,
+            h = self.height
+        );
+        let _ = writeln!(document, "<style>");
+        for font in self.fonts.unique_fonts() {
+            document.push_str(&font_face(font, self.font_mode));
+        }
+        let _ = writeln!(document, "</style>");
+        for element in &self.elements {
+            let _ = writeln!(document, "{}", element);
+        }
+        document.push_str("</svg>\n");
+        Ok(document)
+    }
+
+    fn push_image(&mut self, data: &[u8], mime: &str, x: i64, y: i64, width: u32, height: u32) {
+        self.elements.push(format!(
+            r#"<image x="{}" y="{}" width="{}" height="{}" xlink:href="data:{};base64,{}"/>"#,
+            x, y, width, height, mime, BASE64.encode(data)
+        ));
+    }
+}
+
+impl OverlayRenderer for SvgRenderer<'_> {
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, scale: Scale, role: FontRole, color: Rgba<u8>) {
+        // O visualizador faz o fallback por glifo com a lista de famílias da pilha
+        let stack = self.fonts.stack(role);
+        let font = stack.primary();
+        let families: Vec<String> = stack.fonts().iter().map(|font| format!("'{}'", escape_xml(&font.family))).collect();
+        let weight = if font.weight == 400 { String::new() } else { format!(r#" font-weight="{}""#, font.weight) };
+        // No SVG, `y` é a linha de base; o raster usa o topo da caixa
+        let baseline = y as f32 + font.v_metrics(scale).ascent;
+        self.elements.push(format!(
+            r##"<text x="{}" y="{:.1}" font-family="{}"{} font-size="{:.1}" fill="#{:02x}{:02x}{:02x}"{}>{}</text>"##,
+            x,
+            baseline,
+            families.join(", "),
+            weight,
+            svg_font_size(font, scale),
+            color[0],
+            color[1],
+            color[2],
+            opacity_attribute(color),
+            escape_xml(text)
+        ));
+    }
+
+    fn draw_image(&mut self, image: &RgbaImage, x: i64, y: i64) -> AppResult<()> {
+        let encoded = encoder::encode(image, OutputFormat::Png, &EncoderOptions::default())?;
+        self.push_image(&encoded, OutputFormat::Png.mime_type(), x, y, image.width(), image.height());
+        Ok(())
+    }
+}
+
+/// Declaração `@font-face` para uma família e peso, embutida ou referenciada
+fn font_face(font: &FontAsset, mode: SvgFontMode) -> String {
+    let family = escape_xml(&font.family);
+    let source = match (mode, &font.source) {
+        (SvgFontMode::Embed, _) => format!("url(data:font/ttf;base64,{}) format('truetype')", BASE64.encode(font.data())),
+        (SvgFontMode::Reference, AssetSource::File(path)) => {
+            let absolute = std::fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+            format!("local('{}'), url('file://{}') format('truetype')", family, escape_xml(&absolute.display().to_string()))
+        }
+        (SvgFontMode::Reference, AssetSource::Embedded) => format!("local('{}')", family),
+    };
+    format!("@font-face {{ font-family: '{}'; font-weight: {}; src: {}; }}\n", family, font.weight, source)
+}
+
+/// Converte a escala do rusttype (altura ascendente-descendente) no
+/// `font-size` do SVG (tamanho do em)
+fn svg_font_size(font: &Font<'static>, scale: Scale) -> f32 {
+    let metrics = font.v_metrics_unscaled();
+    let height = metrics.ascent - metrics.descent;
+    if height <= 0.0 {
+        return scale.y;
+    }
+    scale.y * font.units_per_em() as f32 / height
+}
+
+/// Atributo de opacidade, omitido para cores opacas
+fn opacity_attribute(color: Rgba<u8>) -> String {
+    if color[3] == 255 {
+        String::new()
+    } else {
+        format!(r#" fill-opacity="{:.3}""#, color[3] as f32 / 255.0)
+    }
+}
+
+/// Escapa os caracteres reservados do XML
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
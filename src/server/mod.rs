@@ -0,0 +1,507 @@
+// ============================================================================
+// src/server/mod.rs - Modo serve: servidor HTTP local com formulário e API JSON
+// ============================================================================
+
+//! Servidor HTTP local para integrar a renderização a outras aplicações.
+//!
+//! As rotas são tratadas por [`handle_request`], uma função pura de
+//! [`HttpRequest`] para [`HttpResponse`]: o socket (tiny_http) fica só em
+//! [`serve`], e a API pode ser chamada dentro do próprio processo (ver os
+//! testes no fim deste arquivo).
+//!
+//! | Rota           | Corpo                                                | Resposta              |
+//! |----------------|------------------------------------------------------|-----------------------|
+//! | `GET /`        | -                                                    | formulário de upload  |
+//! | `POST /render` | multipart: `photo`, `activity` e opções              | imagem final          |
+//! | `POST /stats`  | multipart com `activity` ou o arquivo cru            | `ActivityData` (JSON) |
+//!
+//! As opções do `/render` usam os nomes das flags da linha de comando
+//! (`format`, `quality`, `preset`, `timezone`, `at-photo`...).
+
+pub mod multipart;
+
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use log::{info, warn};
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use tcx_image_overlay::{
+    parse_activity_bytes, AppError, AppResult, CropFocus, EncoderOptions, FitMode, ImageProcessor, LetterboxFill,
+    Locale, OutputFormat, OutputPreset, PngCompression, PresetOptions, SvgFontMode, Theme, TimezoneOverride, Units,
+    WatermarkPolicy, DEFAULT_OUTPUT_QUALITY,
+};
+
+/// Endereço padrão (apenas local) e tamanho máximo de um upload
+pub const SERVE_DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+const SERVE_MAX_BODY_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Requisição HTTP já lida por completo
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: String,
+    /// Caminho com a query string (ex: "/stats?samples=true")
+    pub url: String,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+#[cfg(test)]
+impl HttpRequest {
+    /// Requisição sem corpo
+    pub fn new(method: &str, url: &str) -> Self {
+        Self { method: method.to_string(), url: url.to_string(), content_type: None, body: Vec::new() }
+    }
+
+    /// Define o corpo e o seu Content-Type
+    pub fn with_body(mut self, content_type: &str, body: Vec<u8>) -> Self {
+        self.content_type = Some(content_type.to_string());
+        self.body = body;
+        self
+    }
+}
+
+/// Resposta HTTP a ser enviada ao cliente
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    fn new(status: u16, content_type: &str, body: Vec<u8>) -> Self {
+        Self { status, headers: vec![("Content-Type".to_string(), content_type.to_string())], body }
+    }
+
+    fn json(status: u16, value: &Value) -> Self {
+        Self::new(status, "application/json", value.to_string().into_bytes())
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &json!({ "error": message }))
+    }
+
+    /// Valor de um cabeçalho (sem diferenciar maiúsculas no nome)
+    #[cfg(test)]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Trata uma requisição: roteamento, execução e conversão de erros em JSON
+pub fn handle_request(request: &HttpRequest) -> HttpResponse {
+    let (path, query) = request.url.split_once('?').unwrap_or((&request.url, ""));
+    let result = match (request.method.as_str(), path) {
+        ("GET", "/") => Ok(HttpResponse::new(200, "text/html; charset=utf-8", upload_form().into_bytes())),
+        ("POST", "/render") => render(request),
+        ("POST", "/stats") => stats(request, query),
+        (_, "/" | "/render" | "/stats") => Ok(HttpResponse::error(405, "Método não permitido")),
+        _ => Ok(HttpResponse::error(404, "Rota não encontrada")),
+    };
+
+    result.unwrap_or_else(|e| {
+        // Falhas de E/S e recursos ausentes são do servidor; as demais vêm de
+        // arquivos ou opções inválidos
+        let status = match e {
+            AppError::IoError(_) | AppError::FileError { .. } | AppError::MissingAsset { .. } | AppError::FontError(_) => 500,
+            AppError::LayoutDoesNotFit { .. } => 422,
+            _ => 400,
+        };
+        HttpResponse::json(status, &json!({ "error": e.to_string(), "code": e.code() }))
+    })
+}
+
+/// `POST /render`: aplica o overlay da atividade à foto e devolve a imagem
+fn render(request: &HttpRequest) -> AppResult<HttpResponse> {
+    let parts = multipart::parse(request.content_type.as_deref().unwrap_or_default(), &request.body)?;
+
+    let mut photo = None;
+    let mut activity = None;
+    let mut options = RenderOptions::default();
+    for part in &parts {
+        match part.name.as_str() {
+            "photo" => photo = Some(part),
+            "activity" => activity = Some(part),
+            name => options.set(name, part.text()?)?,
+        }
+    }
+    let photo = photo.ok_or_else(|| missing_field("photo"))?;
+    let activity = activity.ok_or_else(|| missing_field("activity"))?;
+
+    // Sem `format`, mantém o formato da foto enviada (PNG se não for gerenciado)
+    let photo_name = Path::new(photo.filename.as_deref().unwrap_or_default());
+    let format = options.encoder.format
+        .or_else(|| OutputFormat::from_path(photo_name))
+        .unwrap_or(OutputFormat::Png);
+
+    let activity_data = parse_activity_bytes(&activity.data)?;
+    let mut processor = ImageProcessor::from_bytes(&photo.data)?;
+    options.configure(&mut processor, format);
+    processor.process_activity(&activity_data)?;
+
+    let stem = photo_name.file_stem().and_then(|s| s.to_str()).unwrap_or("foto");
+    let mut response = HttpResponse::new(200, format.mime_type(), processor.encode()?);
+    response.headers.push((
+        "Content-Disposition".to_string(),
+        format!("inline; filename=\"{}-stats-overlay.{}\"", stem.replace('"', ""), format.extension()),
+    ));
+    Ok(response)
+}
+
+/// `POST /stats`: devolve a atividade em JSON; as amostras só com `?samples=true`
+fn stats(request: &HttpRequest, query: &str) -> AppResult<HttpResponse> {
+    let content_type = request.content_type.as_deref().unwrap_or_default();
+    let activity_data = if multipart::boundary(content_type).is_some() {
+        let parts = multipart::parse(content_type, &request.body)?;
+        let activity = parts.iter().find(|part| part.name == "activity").ok_or_else(|| missing_field("activity"))?;
+        parse_activity_bytes(&activity.data)?
+    } else {
+        parse_activity_bytes(&request.body)?
+    };
+
+    let include_samples = match query_value(query, "samples") {
+        Some(value) => parse_bool("samples", value)?,
+        None => false,
+    };
+    let mut value = serde_json::to_value(&activity_data)
+        .map_err(|e| AppError::ParseError(format!("Falha ao serializar a atividade: {}", e)))?;
+    if !include_samples {
+        if let Some(object) = value.as_object_mut() {
+            object.remove("samples");
+        }
+    }
+    Ok(HttpResponse::json(200, &value))
+}
+
+/// Opções do `/render`, com os mesmos nomes e padrões das flags da CLI
+#[derive(Debug, Default)]
+struct RenderOptions {
+    timezone: Option<TimezoneOverride>,
+    at_photo: bool,
+    keep_metadata: bool,
+    strip_gps: bool,
+    describe: bool,
+    encoder: EncoderOptions,
+    preset: Option<OutputPreset>,
+    fit: FitMode,
+    crop_focus: CropFocus,
+    fill: LetterboxFill,
+    svg_fonts: SvgFontMode,
+    theme: Theme,
+    watermark: WatermarkPolicy,
+    units: Units,
+    locale: Locale,
+}
+
+impl RenderOptions {
+    /// Lê um campo de texto do formulário; campos vazios mantêm o padrão
+    fn set(&mut self, name: &str, value: &str) -> AppResult<()> {
+        if value.is_empty() {
+            return Ok(());
+        }
+        let invalid = |e: String| AppError::InvalidFormat(format!("Campo '{}': {}", name, e));
+        match name {
+            "timezone" => self.timezone = Some(value.parse().map_err(invalid)?),
+            "at-photo" => self.at_photo = parse_bool(name, value)?,
+            "keep-metadata" => self.keep_metadata = parse_bool(name, value)?,
+            "strip-gps" => self.strip_gps = parse_bool(name, value)?,
+            "describe" => self.describe = parse_bool(name, value)?,
+            "format" => self.encoder.format = Some(parse_enum(name, value)?),
+            "quality" => {
+                self.encoder.quality = value.parse().ok().filter(|q| (1..=100).contains(q))
+                    .ok_or_else(|| invalid(format!("qualidade deve estar entre 1 e 100 (padrão {})", DEFAULT_OUTPUT_QUALITY)))?;
+            }
+            "png-compression" => self.encoder.png_compression = parse_enum::<PngCompression>(name, value)?,
+            "progressive" => self.encoder.progressive = parse_bool(name, value)?,
+            "preset" => self.preset = Some(parse_enum(name, value)?),
+            "fit" => self.fit = parse_enum(name, value)?,
+            "crop-focus" => self.crop_focus = parse_enum(name, value)?,
+            "fill" => self.fill = value.parse().map_err(invalid)?,
+            "svg-fonts" => self.svg_fonts = parse_enum(name, value)?,
+            "theme" => self.theme = parse_enum(name, value)?,
+            "watermark" => self.watermark = parse_enum(name, value)?,
+            "units" => self.units = parse_enum(name, value)?,
+            "locale" => self.locale = parse_enum(name, value)?,
+            _ => return Err(AppError::InvalidFormat(format!("Campo desconhecido: '{}'", name))),
+        }
+        Ok(())
+    }
+
+    /// Aplica as opções ao processador, como `OverlayArgs::configure` na CLI
+    fn configure(&self, processor: &mut ImageProcessor, format: OutputFormat) {
+        if let Some(preset) = self.preset {
+            processor.apply_preset(&PresetOptions {
+                preset,
+                fit: self.fit,
+                focus: self.crop_focus,
+                fill: self.fill,
+            });
+        }
+
+        if let Some(timezone) = self.timezone {
+            processor.set_timezone(timezone);
+        }
+        processor.set_photo_point(self.at_photo);
+        processor.set_metadata_options(self.keep_metadata, self.strip_gps, self.describe);
+        processor.set_encoder_options(EncoderOptions { format: Some(format), ..self.encoder.clone() });
+        processor.set_svg_font_mode(self.svg_fonts);
+        processor.set_theme(self.theme);
+        processor.set_watermark_policy(self.watermark);
+        processor.set_units(self.units);
+        processor.set_locale(self.locale);
+    }
+}
+
+/// Inicia o servidor em `address` com `workers` threads atendendo requisições
+pub fn serve(address: &str, workers: usize) -> AppResult<()> {
+    let server = tiny_http::Server::http(address)
+        .map_err(|e| AppError::InvalidFormat(format!("Falha ao abrir {}: {}", address, e)))?;
+    let server = Arc::new(server);
+    info!("🌐 Servidor em http://{}/ ({} worker(s), Ctrl+C para sair)", address, workers.max(1));
+
+    let handles: Vec<_> = (0..workers.max(1))
+        .map(|_| {
+            let server = Arc::clone(&server);
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    respond(request);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        let _ = handle.join();
+    }
+    Ok(())
+}
+
+/// Lê a requisição do socket, trata com [`handle_request`] e envia a resposta
+fn respond(mut request: tiny_http::Request) {
+    let started = Instant::now();
+    let method = request.method().as_str().to_string();
+    let url = request.url().to_string();
+    let content_type = request.headers()
+        .iter()
+        .find(|header| header.field.equiv("Content-Type"))
+        .map(|header| header.value.as_str().to_string());
+
+    let response = receive(&method, &url, content_type, request.as_reader());
+    info!("🌐 {} {} → {} ({} ms)", method, url, response.status, started.elapsed().as_millis());
+
+    let mut reply = tiny_http::Response::from_data(response.body).with_status_code(response.status);
+    for (name, value) in &response.headers {
+        if let Ok(header) = tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+            reply.add_header(header);
+        }
+    }
+    if let Err(e) = request.respond(reply) {
+        warn!("⚠️  Falha ao enviar a resposta: {}", e);
+    }
+}
+
+/// Lê o corpo (até `SERVE_MAX_BODY_BYTES`) e trata a requisição com
+/// [`handle_request`]; corpos maiores são recusados com 413
+fn receive<R: Read>(method: &str, url: &str, content_type: Option<String>, reader: R) -> HttpResponse {
+    let mut body = Vec::new();
+    match reader.take(SERVE_MAX_BODY_BYTES + 1).read_to_end(&mut body) {
+        Err(e) => HttpResponse::error(400, &format!("Falha ao ler o corpo: {}", e)),
+        Ok(_) if body.len() as u64 > SERVE_MAX_BODY_BYTES => {
+            HttpResponse::error(413, &format!("Corpo maior que {} MB", SERVE_MAX_BODY_BYTES / (1024 * 1024)))
+        }
+        Ok(_) => handle_request(&HttpRequest { method: method.to_string(), url: url.to_string(), content_type, body }),
+    }
+}
+
+fn missing_field(name: &str) -> AppError {
+    AppError::InvalidFormat(format!("Campo obrigatório ausente: '{}'", name))
+}
+
+/// Valor booleano de formulário ou query string (checkbox envia "on")
+fn parse_bool(name: &str, value: &str) -> AppResult<bool> {
+    match value.to_lowercase().as_str() {
+        "on" | "true" | "1" | "yes" => Ok(true),
+        "off" | "false" | "0" | "no" => Ok(false),
+        _ => Err(AppError::InvalidFormat(format!("Campo '{}': esperado true ou false, recebido '{}'", name, value))),
+    }
+}
+
+/// Valor de um enum da CLI pelo nome (ex: "webp-lossy", "instagram-story")
+fn parse_enum<T: ValueEnum>(name: &str, value: &str) -> AppResult<T> {
+    T::from_str(value, true).map_err(|_| {
+        AppError::InvalidFormat(format!("Campo '{}': valor inválido '{}' (use {})", name, value, variant_names::<T>().join(", ")))
+    })
+}
+
+fn variant_names<T: ValueEnum>() -> Vec<String> {
+    T::value_variants()
+        .iter()
+        .filter_map(|variant| variant.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect()
+}
+
+/// Valor de um parâmetro da query string (sem decodificação de %XX)
+fn query_value<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, "true"));
+        (key == name).then_some(value)
+    })
+}
+
+/// Formulário HTML de upload; as listas vêm dos mesmos enums da CLI
+fn upload_form() -> String {
+    let select = |name: &str, values: Vec<String>, optional: bool| {
+        let mut options: Vec<String> = values.iter().map(|v| format!("<option>{}</option>", v)).collect();
+        if optional {
+            options.insert(0, "<option value=\"\">(padrão)</option>".to_string());
+        }
+        format!("<label>{} <select name=\"{}\">{}</select></label>", name, name, options.concat())
+    };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="pt-BR">
+<head><meta charset="utf-8"><title>TCX/FIT Image Overlay</title></head>
+<body>
+<h1>TCX/FIT Image Overlay</h1>
+<form method="post" action="/render" enctype="multipart/form-data">
+<p><label>Foto <input type="file" name="photo" accept="image/*" required></label></p>
+<p><label>Atividade (TCX/FIT/GPX, .gz, .zip) <input type="file" name="activity" required></label></p>
+<p>{format} <label>quality <input type="number" name="quality" min="1" max="100" value="{quality}"></label></p>
+<p>{theme} {watermark} {units} {locale}</p>
+<p>{preset} {fit} {crop_focus} <label>fill <input type="text" name="fill" placeholder="blur ou #202020"></label></p>
+<p><label>timezone <input type="text" name="timezone" placeholder="America/Sao_Paulo"></label></p>
+<p><label><input type="checkbox" name="at-photo"> at-photo</label>
+<label><input type="checkbox" name="keep-metadata"> keep-metadata</label>
+<label><input type="checkbox" name="strip-gps"> strip-gps</label>
+<label><input type="checkbox" name="describe"> describe</label></p>
+<p><button type="submit">Gerar imagem</button></p>
+</form>
+</body>
+</html>
+"#,
+        format = select("format", variant_names::<OutputFormat>(), true),
+        quality = DEFAULT_OUTPUT_QUALITY,
+        preset = select("preset", variant_names::<OutputPreset>(), true),
+        fit = select("fit", variant_names::<FitMode>(), false),
+        crop_focus = select("crop-focus", variant_names::<CropFocus>(), false),
+        theme = select("theme", variant_names::<Theme>(), false),
+        watermark = select("watermark", variant_names::<WatermarkPolicy>(), false),
+        units = select("units", variant_names::<Units>(), false),
+        locale = select("locale", variant_names::<Locale>(), false),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Cursor};
+
+    const BOUNDARY: &str = "----overlay-teste";
+
+    /// Atividade mínima: dois pontos com FC, distância e posição
+    const ACTIVITY_TCX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+<Activities><Activity Sport="Running"><Id>2024-08-20T09:00:00Z</Id>
+<Lap StartTime="2024-08-20T09:00:00Z"><TotalTimeSeconds>600</TotalTimeSeconds><DistanceMeters>2000</DistanceMeters><Calories>150</Calories><AverageHeartRateBpm><Value>150</Value></AverageHeartRateBpm><MaximumHeartRateBpm><Value>160</Value></MaximumHeartRateBpm><Intensity>Active</Intensity><TriggerMethod>Manual</TriggerMethod>
+<Track>
+<Trackpoint><Time>2024-08-20T09:00:00Z</Time><Position><LatitudeDegrees>-10.18</LatitudeDegrees><LongitudeDegrees>-48.33</LongitudeDegrees></Position><DistanceMeters>0</DistanceMeters><HeartRateBpm><Value>140</Value></HeartRateBpm></Trackpoint>
+<Trackpoint><Time>2024-08-20T09:10:00Z</Time><Position><LatitudeDegrees>-10.17</LatitudeDegrees><LongitudeDegrees>-48.33</LongitudeDegrees></Position><DistanceMeters>2000</DistanceMeters><HeartRateBpm><Value>160</Value></HeartRateBpm></Trackpoint>
+</Track></Lap>
+<Creator><Name>Forerunner 265</Name></Creator></Activity></Activities></TrainingCenterDatabase>"#;
+
+    /// Monta um corpo multipart/form-data com (nome, arquivo, conteúdo)
+    fn multipart_body(parts: &[(&str, Option<&str>, &[u8])]) -> (String, Vec<u8>) {
+        let mut body = Vec::new();
+        for (name, filename, data) in parts {
+            body.extend_from_slice(format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"", BOUNDARY, name).as_bytes());
+            if let Some(filename) = filename {
+                body.extend_from_slice(format!("; filename=\"{}\"", filename).as_bytes());
+            }
+            body.extend_from_slice(b"\r\n\r\n");
+            body.extend_from_slice(data);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+        (format!("multipart/form-data; boundary={}", BOUNDARY), body)
+    }
+
+    fn photo_png() -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(1080, 1080, image::Rgba([40, 80, 120, 255]));
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, image::ImageOutputFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn get_root_returns_upload_form() {
+        let response = handle_request(&HttpRequest::new("GET", "/"));
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header("Content-Type"), Some("text/html; charset=utf-8"));
+    }
+
+    #[test]
+    fn post_render_returns_encoded_image() {
+        let photo = photo_png();
+        let (content_type, body) = multipart_body(&[
+            ("photo", Some("corrida.png"), &photo),
+            ("activity", Some("treino.tcx"), ACTIVITY_TCX.as_bytes()),
+            ("format", None, b"jpeg"),
+        ]);
+        let response = handle_request(&HttpRequest::new("POST", "/render").with_body(&content_type, body));
+
+        assert_eq!(response.status, 200, "{}", String::from_utf8_lossy(&response.body));
+        assert_eq!(response.header("Content-Type"), Some("image/jpeg"));
+        assert_eq!(
+            response.header("Content-Disposition"),
+            Some("inline; filename=\"corrida-stats-overlay.jpg\"")
+        );
+        let rendered = image::load_from_memory(&response.body).unwrap();
+        assert_eq!((rendered.width(), rendered.height()), (1080, 1080));
+    }
+
+    #[test]
+    fn post_stats_returns_activity_json() {
+        let request = HttpRequest::new("POST", "/stats").with_body("application/octet-stream", ACTIVITY_TCX.as_bytes().to_vec());
+        let response = handle_request(&request);
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header("Content-Type"), Some("application/json"));
+        let value: Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(value["max_hr"], 160);
+        assert!(value.get("samples").is_none());
+    }
+
+    #[test]
+    fn post_stats_includes_samples_on_request() {
+        let (content_type, body) = multipart_body(&[("activity", Some("treino.tcx"), ACTIVITY_TCX.as_bytes())]);
+        let response = handle_request(&HttpRequest::new("POST", "/stats?samples=true").with_body(&content_type, body));
+
+        let value: Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(value["samples"].as_array().map(Vec::len), Some(2));
+    }
+
+    #[test]
+    fn post_stats_rejects_corrupt_activity() {
+        let request = HttpRequest::new("POST", "/stats").with_body("application/octet-stream", b"nada".to_vec());
+        let response = handle_request(&request);
+
+        assert_eq!(response.status, 400);
+        let value: Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(value["code"], "invalid-input");
+    }
+
+    #[test]
+    fn oversized_body_is_rejected_before_handling() {
+        let body = io::repeat(0).take(SERVE_MAX_BODY_BYTES + 1);
+        let response = receive("POST", "/stats", Some("application/octet-stream".to_string()), body);
+        assert_eq!(response.status, 413);
+    }
+}
@@ -0,0 +1,109 @@
+// ============================================================================
+// src/server/multipart.rs - Leitura de formulários multipart/form-data
+// ============================================================================
+
+use tcx_image_overlay::{AppError, AppResult};
+
+/// Campo de um formulário multipart (arquivo ou valor de texto)
+#[derive(Debug, Clone)]
+pub struct FormPart {
+    pub name: String,
+    /// Nome do arquivo enviado; `None` para campos de texto
+    pub filename: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl FormPart {
+    /// Conteúdo do campo como texto (sem espaços nas pontas)
+    pub fn text(&self) -> AppResult<&str> {
+        std::str::from_utf8(&self.data)
+            .map(str::trim)
+            .map_err(|_| AppError::InvalidFormat(format!("Campo '{}' não está em UTF-8", self.name)))
+    }
+}
+
+/// Extrai o `boundary` do cabeçalho Content-Type (`multipart/form-data; boundary=...`)
+pub fn boundary(content_type: &str) -> Option<&str> {
+    let (mime, params) = content_type.split_once(';')?;
+    if !mime.trim().eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    params.split(';').find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim().eq_ignore_ascii_case("boundary").then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Separa o corpo multipart nos seus campos
+pub fn parse(content_type: &str, body: &[u8]) -> AppResult<Vec<FormPart>> {
+    let boundary = boundary(content_type)
+        .filter(|b| !b.is_empty())
+        .ok_or_else(|| AppError::InvalidFormat("Envie o formulário como multipart/form-data".to_string()))?;
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let malformed = || AppError::InvalidFormat("Corpo multipart malformado".to_string());
+
+    let mut position = find(body, &delimiter, 0).ok_or_else(malformed)? + delimiter.len();
+    let mut parts = Vec::new();
+    loop {
+        // "--" depois do delimitador encerra o formulário
+        if body[position..].starts_with(b"--") {
+            break;
+        }
+        let headers_start = position + skip_line_break(&body[position..]);
+        let headers_end = find(body, b"\r\n\r\n", headers_start).ok_or_else(malformed)?;
+        let headers = std::str::from_utf8(&body[headers_start..headers_end]).map_err(|_| malformed())?;
+
+        let data_start = headers_end + 4;
+        let next = find(body, &delimiter, data_start).ok_or_else(malformed)?;
+        // O CRLF antes do delimitador pertence à separação, não aos dados
+        let data_end = if body[..next].ends_with(b"\r\n") { next - 2 } else { next };
+
+        let (name, filename) = content_disposition(headers).ok_or_else(malformed)?;
+        parts.push(FormPart { name, filename, data: body[data_start..data_end.max(data_start)].to_vec() });
+        position = next + delimiter.len();
+    }
+    Ok(parts)
+}
+
+/// Lê `name` e `filename` do cabeçalho Content-Disposition do campo
+fn content_disposition(headers: &str) -> Option<(String, Option<String>)> {
+    let line = headers.lines().find(|line| {
+        line.split_once(':')
+            .is_some_and(|(key, _)| key.trim().eq_ignore_ascii_case("content-disposition"))
+    })?;
+    let (_, value) = line.split_once(':')?;
+
+    let mut name = None;
+    let mut filename = None;
+    for param in value.split(';').skip(1) {
+        let Some((key, raw)) = param.split_once('=') else {
+            continue;
+        };
+        let raw = raw.trim().trim_matches('"').to_string();
+        match key.trim().to_lowercase().as_str() {
+            "name" => name = Some(raw),
+            "filename" => filename = Some(raw),
+            _ => {}
+        }
+    }
+    Some((name?, filename))
+}
+
+/// Tamanho da quebra de linha no início do trecho (CRLF ou LF)
+fn skip_line_break(data: &[u8]) -> usize {
+    if data.starts_with(b"\r\n") {
+        2
+    } else if data.starts_with(b"\n") {
+        1
+    } else {
+        0
+    }
+}
+
+/// Posição da primeira ocorrência de `needle` a partir de `from`
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack.get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|index| from + index)
+}
@@ -0,0 +1,276 @@
+// ============================================================================
+// src/stats.rs - Exportação das estatísticas da atividade (JSON/CSV), sem imagem
+// ============================================================================
+
+use chrono::{DateTime, FixedOffset, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io;
+
+use crate::cli::StatsArgs;
+use tcx_image_overlay::{
+    heart_rate_zones, parse_activity_file_entry, parse_activity_reader, power_zones, resolve_start_time, ActivityData,
+    AppError, AppResult, HeartRateZone, Lap, PowerZone, Sample, HR_ZONE_LOWER_PERCENTS, POWER_ZONE_LOWER_PERCENTS,
+    STDIO_PATH,
+};
+
+/// Formato da exportação
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum StatsFormat {
+    /// Documento com resumo, voltas, zonas e (opcionalmente) amostras
+    #[default]
+    Json,
+    /// Uma tabela por vez, escolhida com --table
+    Csv,
+}
+
+/// Tabela exportada no formato CSV
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum StatsTable {
+    #[default]
+    Summary,
+    Laps,
+    Zones,
+    /// Zonas de potência; exige `--ftp` (ou `ftp` no arquivo de configuração)
+    PowerZones,
+    Samples,
+}
+
+/// Resumo da atividade, com os valores derivados usados no overlay
+#[derive(Debug, Serialize)]
+struct Summary<'a> {
+    sport: Option<&'a str>,
+    device_name: &'a str,
+    start_time: DateTime<Utc>,
+    start_time_local: DateTime<FixedOffset>,
+    timezone_source: String,
+    end_time: DateTime<Utc>,
+    total_time_seconds: f64,
+    moving_time_seconds: Option<f64>,
+    distance_meters: Option<f64>,
+    elevation_gain_meters: Option<f64>,
+    moving_distance_meters: Option<f64>,
+    moving_avg_speed_mps: Option<f64>,
+    calories: u16,
+    avg_hr: u8,
+    max_hr: u8,
+    /// Média das amostras com potência; `None` sem medidor de potência
+    avg_power_watts: Option<u16>,
+    max_power_watts: Option<u16>,
+    /// Média das amostras com cadência (sem zeros), como o dispositivo registra
+    avg_cadence: Option<u8>,
+    lap_count: usize,
+    sample_count: usize,
+    timer_event_count: usize,
+}
+
+/// Documento JSON completo
+#[derive(Debug, Serialize)]
+struct StatsReport<'a> {
+    summary: Summary<'a>,
+    laps: &'a [Lap],
+    /// Zonas calculadas com `--max-hr`/`--hr-zones` ou com a FC máxima da atividade
+    zones: Vec<HeartRateZone>,
+    /// Zonas de potência, só quando o FTP é informado
+    #[serde(skip_serializing_if = "Option::is_none")]
+    power_zones: Option<Vec<PowerZone>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    samples: Option<&'a [Sample]>,
+}
+
+/// Executa o subcomando `stats`
+pub fn run(args: &StatsArgs) -> AppResult<()> {
+    let activity_data = match (args.activity_path.as_os_str() == STDIO_PATH, &args.entry) {
+        (true, Some(_)) => {
+            return Err(AppError::InvalidFormat(
+                "--entry não se aplica à atividade lida da entrada padrão (-)".to_string()
+            ));
+        }
+        (true, None) => parse_activity_reader(io::stdin().lock())?,
+        (false, entry) => parse_activity_file_entry(&args.activity_path, entry.as_deref())?,
+    };
+
+    let zone_percents = args.hr_zones.as_deref().unwrap_or(&HR_ZONE_LOWER_PERCENTS);
+    if zone_percents.windows(2).any(|pair| pair[0] >= pair[1]) || zone_percents.iter().any(|p| *p > 100) {
+        return Err(AppError::InvalidFormat(
+            "As zonas de FC devem ser percentuais crescentes até 100 (ex: 50,60,70,80,90)".to_string()
+        ));
+    }
+
+    if args.format == StatsFormat::Csv && args.table == StatsTable::PowerZones && args.ftp.is_none() {
+        return Err(AppError::InvalidFormat(
+            "A tabela power-zones exige o FTP (--ftp ou ftp no arquivo de configuração)".to_string()
+        ));
+    }
+
+    let max_hr = args.max_hr.unwrap_or(activity_data.max_hr);
+    let report = StatsReport {
+        summary: summary(&activity_data, args),
+        laps: &activity_data.laps,
+        zones: heart_rate_zones(&activity_data.samples, &activity_data.timer_events, max_hr, zone_percents),
+        power_zones: args.ftp.map(|ftp| {
+            power_zones(&activity_data.samples, &activity_data.timer_events, ftp, &POWER_ZONE_LOWER_PERCENTS)
+        }),
+        samples: args.samples.then_some(activity_data.samples.as_slice()),
+    };
+
+    match args.format {
+        StatsFormat::Json => {
+            let json = serde_json::to_string_pretty(&report)
+                .map_err(|e| AppError::ParseError(format!("Falha ao serializar as estatísticas: {}", e)))?;
+            println!("{}", json);
+        }
+        StatsFormat::Csv => print_csv(&report, &activity_data, args.table),
+    }
+    Ok(())
+}
+
+fn summary<'a>(activity_data: &'a ActivityData, args: &StatsArgs) -> Summary<'a> {
+    let (start_time_local, timezone_source) = resolve_start_time(activity_data, args.timezone.as_ref());
+
+    Summary {
+        sport: activity_data.sport.as_deref(),
+        device_name: &activity_data.device_name,
+        start_time: activity_data.start_time,
+        start_time_local,
+        timezone_source: timezone_source.to_string(),
+        end_time: activity_data.end_time(),
+        total_time_seconds: activity_data.total_time_seconds,
+        moving_time_seconds: activity_data.moving_time_seconds,
+        distance_meters: activity_data.total_distance_meters(),
+        elevation_gain_meters: activity_data.elevation_gain_meters(),
+        moving_distance_meters: activity_data.moving_distance_meters,
+        moving_avg_speed_mps: activity_data.moving_avg_speed_mps(),
+        calories: activity_data.calories,
+        avg_hr: activity_data.avg_hr,
+        max_hr: activity_data.max_hr,
+        avg_power_watts: activity_data.avg_power_watts(),
+        max_power_watts: activity_data.max_power_watts(),
+        avg_cadence: activity_data.avg_cadence(),
+        lap_count: activity_data.laps.len(),
+        sample_count: activity_data.samples.len(),
+        timer_event_count: activity_data.timer_events.len(),
+    }
+}
+
+/// Imprime uma das tabelas em CSV (cabeçalho + linhas; campos ausentes ficam vazios)
+fn print_csv(report: &StatsReport, activity_data: &ActivityData, table: StatsTable) {
+    let opt = |value: Option<String>| value.unwrap_or_default();
+    let rows: Vec<Vec<String>> = match table {
+        StatsTable::Summary => {
+            let s = &report.summary;
+            vec![
+                vec![
+                    "sport", "device_name", "start_time", "start_time_local", "timezone_source", "end_time",
+                    "total_time_seconds", "moving_time_seconds", "distance_meters", "elevation_gain_meters",
+                    "moving_distance_meters", "moving_avg_speed_mps", "calories", "avg_hr", "max_hr", "avg_power_watts",
+                    "max_power_watts", "avg_cadence", "lap_count", "sample_count", "timer_event_count",
+                ].into_iter().map(String::from).collect(),
+                vec![
+                    opt(s.sport.map(String::from)),
+                    s.device_name.to_string(),
+                    s.start_time.to_rfc3339(),
+                    s.start_time_local.to_rfc3339(),
+                    s.timezone_source.clone(),
+                    s.end_time.to_rfc3339(),
+                    s.total_time_seconds.to_string(),
+                    opt(s.moving_time_seconds.map(|v| v.to_string())),
+                    opt(s.distance_meters.map(|v| v.to_string())),
+                    opt(s.elevation_gain_meters.map(|v| v.to_string())),
+                    opt(s.moving_distance_meters.map(|v| v.to_string())),
+                    opt(s.moving_avg_speed_mps.map(|v| v.to_string())),
+                    s.calories.to_string(),
+                    s.avg_hr.to_string(),
+                    s.max_hr.to_string(),
+                    opt(s.avg_power_watts.map(|v| v.to_string())),
+                    opt(s.max_power_watts.map(|v| v.to_string())),
+                    opt(s.avg_cadence.map(|v| v.to_string())),
+                    s.lap_count.to_string(),
+                    s.sample_count.to_string(),
+                    s.timer_event_count.to_string(),
+                ],
+            ]
+        }
+        StatsTable::Laps => std::iter::once(
+            ["lap", "start_time", "total_time_seconds", "distance_meters", "calories", "avg_hr", "max_hr"]
+                .into_iter().map(String::from).collect(),
+        )
+        .chain(report.laps.iter().enumerate().map(|(index, lap)| vec![
+            (index + 1).to_string(),
+            lap.start_time.to_rfc3339(),
+            lap.total_time_seconds.to_string(),
+            opt(lap.distance_meters.map(|v| v.to_string())),
+            opt(lap.calories.map(|v| v.to_string())),
+            opt(lap.avg_hr.map(|v| v.to_string())),
+            opt(lap.max_hr.map(|v| v.to_string())),
+        ]))
+        .collect(),
+        StatsTable::Zones => std::iter::once(
+            ["zone", "min_bpm", "max_bpm", "seconds"].into_iter().map(String::from).collect(),
+        )
+        .chain(report.zones.iter().map(|zone| vec![
+            zone.zone.to_string(),
+            zone.min_bpm.to_string(),
+            opt(zone.max_bpm.map(|v| v.to_string())),
+            zone.seconds.to_string(),
+        ]))
+        .collect(),
+        StatsTable::PowerZones => std::iter::once(
+            ["zone", "min_watts", "max_watts", "seconds"].into_iter().map(String::from).collect(),
+        )
+        .chain(report.power_zones.iter().flatten().map(|zone| vec![
+            zone.zone.to_string(),
+            zone.min_watts.to_string(),
+            opt(zone.max_watts.map(|v| v.to_string())),
+            zone.seconds.to_string(),
+        ]))
+        .collect(),
+        StatsTable::Samples => std::iter::once(
+            [
+                "timestamp", "distance_meters", "speed_mps", "heart_rate", "altitude_meters", "latitude", "longitude",
+                "power_watts", "cadence",
+            ].into_iter().map(String::from).collect(),
+        )
+        .chain(activity_data.samples.iter().map(|sample| vec![
+            sample.timestamp.to_rfc3339(),
+            opt(sample.distance_meters.map(|v| v.to_string())),
+            opt(sample.speed_mps.map(|v| v.to_string())),
+            opt(sample.heart_rate.map(|v| v.to_string())),
+            opt(sample.altitude_meters.map(|v| v.to_string())),
+            opt(sample.latitude.map(|v| v.to_string())),
+            opt(sample.longitude.map(|v| v.to_string())),
+            opt(sample.power_watts.map(|v| v.to_string())),
+            opt(sample.cadence.map(|v| v.to_string())),
+        ]))
+        .collect(),
+    };
+
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        println!("{}", fields.join(","));
+    }
+}
+
+/// Escapa um campo CSV (aspas quando contém vírgula, aspas ou quebra de linha)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("Forerunner 265"), "Forerunner 265");
+        assert_eq!(csv_field(""), "");
+        assert_eq!(csv_field("Edge 540, Garmin"), "\"Edge 540, Garmin\"");
+        assert_eq!(csv_field("Edge \"540\""), "\"Edge \"\"540\"\"\"");
+        assert_eq!(csv_field("linha 1\nlinha 2"), "\"linha 1\nlinha 2\"");
+        assert_eq!(csv_field("a\r\nb"), "\"a\r\nb\"");
+    }
+}
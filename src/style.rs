@@ -0,0 +1,72 @@
+// ============================================================================
+// src/style.rs - Tema de cores do overlay e política da marca d'água
+// ============================================================================
+
+use clap::ValueEnum;
+use image::Rgba;
+use crate::constants::*;
+
+/// Luminância (0-255) a partir da qual o fundo é considerado claro
+pub const LIGHT_BACKGROUND_LUMINANCE: f32 = 128.0;
+
+/// Cores do texto e da sombra do overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Theme {
+    /// Texto claro com sombra escura (padrão, para a maioria das fotos)
+    #[default]
+    Light,
+    /// Texto escuro com sombra clara, para fotos claras (neve, céu, praia)
+    Dark,
+    /// Escolhe entre light e dark pela luminância do fundo das estatísticas
+    Auto,
+}
+
+impl Theme {
+    /// Tema efetivo; `auto` usa a luminância média sob as estatísticas
+    pub fn resolve(self, background_luminance: f32) -> Theme {
+        match self {
+            Theme::Auto if background_luminance >= LIGHT_BACKGROUND_LUMINANCE => Theme::Dark,
+            Theme::Auto => Theme::Light,
+            theme => theme,
+        }
+    }
+
+    /// Nome do tema (ex: no `--explain`)
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::Auto => "auto",
+        }
+    }
+
+    /// Cores de valores, sombras e subtextos; os ícones mantêm as cores de cada estatística
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Dark => Palette { text: DARK_TEXT_COLOR, shadow: LIGHT_SHADOW_COLOR, sub_text: DARK_SUBTEXT_COLOR },
+            Theme::Light | Theme::Auto => Palette { text: TEXT_COLOR, shadow: SHADOW_COLOR, sub_text: SUBTEXT_COLOR },
+        }
+    }
+}
+
+/// Cores usadas ao desenhar as estatísticas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub text: Rgba<u8>,
+    pub shadow: Rgba<u8>,
+    pub sub_text: Rgba<u8>,
+}
+
+/// Quando e em que cor desenhar a marca d'água (só em atividades de dispositivos Garmin)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum WatermarkPolicy {
+    /// Branca sobre fundo escuro, preta sobre fundo claro
+    #[default]
+    Auto,
+    /// Sempre a marca d'água branca
+    White,
+    /// Sempre a marca d'água preta
+    Black,
+    /// Sem marca d'água; as estatísticas descem até a margem inferior
+    None,
+}
@@ -0,0 +1,269 @@
+// ============================================================================
+// src/timezone.rs - Resolução do fuso horário da atividade
+// ============================================================================
+
+use chrono::{DateTime, FixedOffset, Local, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use tzf_rs::DefaultFinder;
+
+use crate::parsers::ActivityData;
+
+/// Fuso horário informado pelo usuário via `--timezone`
+#[derive(Debug, Clone, Copy)]
+pub enum TimezoneOverride {
+    /// Nome IANA (ex: "America/Sao_Paulo"), respeitando horário de verão
+    Named(Tz),
+    /// Deslocamento fixo (ex: "+02:00", "UTC-3")
+    Fixed(FixedOffset),
+}
+
+/// Origem do fuso horário efetivamente aplicado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimezoneSource {
+    Override,
+    Device,
+    /// Fuso IANA encontrado pela coordenada do primeiro ponto GPS
+    GpsLookup(Tz),
+    /// Faixa náutica pela longitude, sem fuso IANA próximo
+    GpsEstimate,
+    System,
+}
+
+impl fmt::Display for TimezoneSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimezoneSource::Override => write!(f, "--timezone"),
+            TimezoneSource::Device => write!(f, "horário local do dispositivo"),
+            TimezoneSource::GpsLookup(tz) => write!(f, "{} pelo primeiro ponto GPS", tz),
+            TimezoneSource::GpsEstimate => write!(f, "estimativa pela longitude do GPS"),
+            TimezoneSource::System => write!(f, "fuso do sistema"),
+        }
+    }
+}
+
+impl FromStr for TimezoneOverride {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let trimmed = value.trim();
+        if let Ok(tz) = trimmed.parse::<Tz>() {
+            return Ok(TimezoneOverride::Named(tz));
+        }
+
+        // Aceita "+02:00", "-0300", "+2", "UTC-3", "GMT+5:30"
+        let offset_part = trimmed
+            .strip_prefix("UTC")
+            .or_else(|| trimmed.strip_prefix("GMT"))
+            .unwrap_or(trimmed);
+        parse_fixed_offset(offset_part)
+            .map(TimezoneOverride::Fixed)
+            .ok_or_else(|| format!(
+                "Fuso horário inválido: '{}'. Use um nome IANA (ex: America/Sao_Paulo) ou deslocamento (ex: -03:00)",
+                value
+            ))
+    }
+}
+
+/// Converte um deslocamento textual ("+02:00", "-0300", "+2") em `FixedOffset`
+fn parse_fixed_offset(value: &str) -> Option<FixedOffset> {
+    let (sign, rest) = match value.chars().next()? {
+        '+' => (1, &value[1..]),
+        '-' => (-1, &value[1..]),
+        _ => return None,
+    };
+    // Apenas dígitos ASCII (e um ':'): evita sinais em "+05:-3" e fatiar
+    // caracteres multibyte
+    if !rest.chars().all(|c| c.is_ascii_digit() || c == ':') {
+        return None;
+    }
+
+    let digits = |part: &str| -> Option<i32> {
+        if part.is_empty() || part.len() > 2 {
+            return None;
+        }
+        part.parse().ok()
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((h, m)) if m.len() == 2 => (digits(h)?, digits(m)?),
+        Some(_) => return None,
+        None if rest.len() == 4 => (digits(rest.get(..2)?)?, digits(rest.get(2..)?)?),
+        None => (digits(rest)?, 0),
+    };
+
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Resolve o horário de início da atividade no fuso em que ela foi registrada.
+///
+/// Ordem de prioridade: `--timezone`, horário local gravado pelo dispositivo
+/// (FIT `activity.local_timestamp`), fuso IANA do primeiro ponto GPS (busca
+/// offline) e, por último, o fuso da máquina.
+pub fn resolve_start_time(
+    activity_data: &ActivityData,
+    timezone_override: Option<&TimezoneOverride>,
+) -> (DateTime<FixedOffset>, TimezoneSource) {
+    let (offset, source) = resolve_offset(activity_data, activity_data.start_time, timezone_override);
+    (activity_data.start_time.with_timezone(&offset), source)
+}
+
+/// Resolve o deslocamento UTC aplicável a um instante da atividade
+pub fn resolve_offset(
+    activity_data: &ActivityData,
+    at: DateTime<Utc>,
+    timezone_override: Option<&TimezoneOverride>,
+) -> (FixedOffset, TimezoneSource) {
+    if let Some(timezone_override) = timezone_override {
+        let offset = match timezone_override {
+            TimezoneOverride::Named(tz) => tz.offset_from_utc_datetime(&at.naive_utc()).fix(),
+            TimezoneOverride::Fixed(offset) => *offset,
+        };
+        return (offset, TimezoneSource::Override);
+    }
+
+    if let Some(offset) = activity_data.utc_offset_seconds.and_then(FixedOffset::east_opt) {
+        return (offset, TimezoneSource::Device);
+    }
+
+    if let Some(resolved) = lookup_offset_from_gps(activity_data, at) {
+        return resolved;
+    }
+
+    (Local.offset_from_utc_datetime(&at.naive_utc()).fix(), TimezoneSource::System)
+}
+
+/// Fuso IANA do primeiro ponto com GPS, com o horário de verão resolvido
+/// no instante `at`. Fora das fronteiras conhecidas, usa a faixa náutica de
+/// 15° da longitude.
+fn lookup_offset_from_gps(activity_data: &ActivityData, at: DateTime<Utc>) -> Option<(FixedOffset, TimezoneSource)> {
+    let (latitude, longitude) = activity_data.samples.iter()
+        .find_map(|sample| Some((sample.latitude?, sample.longitude?)))
+        .filter(|(latitude, longitude)| latitude.is_finite() && longitude.is_finite())?;
+
+    if let Some(tz) = lookup_timezone(latitude, longitude) {
+        let offset = tz.offset_from_utc_datetime(&at.naive_utc()).fix();
+        return Some((offset, TimezoneSource::GpsLookup(tz)));
+    }
+
+    let hours = (longitude / 15.0).round().clamp(-12.0, 12.0) as i32;
+    FixedOffset::east_opt(hours * 3600).map(|offset| (offset, TimezoneSource::GpsEstimate))
+}
+
+/// Busca offline do fuso de uma coordenada pelas fronteiras dos fusos IANA
+/// (dados do timezone-boundary-builder embutidos pelo `tzf-rs`)
+pub fn lookup_timezone(latitude: f64, longitude: f64) -> Option<Tz> {
+    static FINDER: OnceLock<DefaultFinder> = OnceLock::new();
+    let finder = FINDER.get_or_init(DefaultFinder::new);
+    finder.get_tz_name(longitude, latitude).parse::<Tz>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::Sample;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 8, 20, 9, 0, 0).unwrap()
+    }
+
+    fn activity(utc_offset_seconds: Option<i32>, position: Option<(f64, f64)>) -> ActivityData {
+        ActivityData {
+            total_time_seconds: 3600.0,
+            calories: 0,
+            avg_hr: 0,
+            max_hr: 0,
+            start_time: start(),
+            device_name: String::new(),
+            sport: None,
+            utc_offset_seconds,
+            moving_time_seconds: None,
+            moving_distance_meters: None,
+            laps: Vec::new(),
+            samples: vec![Sample {
+                timestamp: start(),
+                latitude: position.map(|(latitude, _)| latitude),
+                longitude: position.map(|(_, longitude)| longitude),
+                ..Sample::default()
+            }],
+            timer_events: Vec::new(),
+        }
+    }
+
+    fn hours(hours: i32) -> FixedOffset {
+        FixedOffset::east_opt(hours * 3600).unwrap()
+    }
+
+    const SAO_PAULO: (f64, f64) = (-23.55, -46.63);
+
+    #[test]
+    fn override_accepts_iana_names() {
+        let parsed = " America/Sao_Paulo ".parse::<TimezoneOverride>().unwrap();
+        assert!(matches!(parsed, TimezoneOverride::Named(chrono_tz::America::Sao_Paulo)));
+    }
+
+    #[test]
+    fn override_accepts_fixed_offsets() {
+        let fixed = |value: &str| match value.parse::<TimezoneOverride>() {
+            Ok(TimezoneOverride::Fixed(offset)) => offset.local_minus_utc(),
+            other => panic!("{}: {:?}", value, other),
+        };
+        assert_eq!(fixed("+05:30"), 5 * 3600 + 30 * 60);
+        assert_eq!(fixed("-3"), -3 * 3600);
+        assert_eq!(fixed("-0300"), -3 * 3600);
+        assert_eq!(fixed("UTC+2"), 2 * 3600);
+        assert_eq!(fixed("GMT-5:30"), -(5 * 3600 + 30 * 60));
+    }
+
+    #[test]
+    fn override_rejects_invalid_input() {
+        for value in ["", "Sao_Paulo", "3", "+15", "+05:60", "+05:3", "+05:-3", "+0٣", "UTC+"] {
+            assert!(value.parse::<TimezoneOverride>().is_err(), "{:?} deveria ser inválido", value);
+        }
+    }
+
+    #[test]
+    fn override_wins_over_everything() {
+        let timezone_override = TimezoneOverride::Fixed(hours(9));
+        let resolved = resolve_offset(&activity(Some(3600), Some(SAO_PAULO)), start(), Some(&timezone_override));
+        assert_eq!(resolved, (hours(9), TimezoneSource::Override));
+    }
+
+    #[test]
+    fn named_override_applies_daylight_saving_at_the_instant() {
+        let timezone_override = TimezoneOverride::Named(chrono_tz::Europe::Lisbon);
+        let winter = Utc.with_ymd_and_hms(2024, 1, 20, 9, 0, 0).unwrap();
+        assert_eq!(resolve_offset(&activity(None, None), start(), Some(&timezone_override)).0, hours(1));
+        assert_eq!(resolve_offset(&activity(None, None), winter, Some(&timezone_override)).0, hours(0));
+    }
+
+    #[test]
+    fn device_offset_wins_over_gps() {
+        let resolved = resolve_offset(&activity(Some(3600), Some(SAO_PAULO)), start(), None);
+        assert_eq!(resolved, (hours(1), TimezoneSource::Device));
+    }
+
+    #[test]
+    fn gps_lookup_finds_the_iana_zone() {
+        let resolved = resolve_offset(&activity(None, Some(SAO_PAULO)), start(), None);
+        assert_eq!(resolved, (hours(-3), TimezoneSource::GpsLookup(chrono_tz::America::Sao_Paulo)));
+    }
+
+    #[test]
+    fn gps_outside_known_zones_uses_the_nautical_band() {
+        // Latitude fora do globo: nenhum fuso IANA, só a faixa de 15° da longitude
+        let resolved = resolve_offset(&activity(None, Some((95.0, -46.63))), start(), None);
+        assert_eq!(resolved, (hours(-3), TimezoneSource::GpsEstimate));
+    }
+
+    #[test]
+    fn without_override_device_or_gps_uses_the_system_zone() {
+        let resolved = resolve_offset(&activity(None, None), start(), None);
+        assert_eq!(resolved, (Local.offset_from_utc_datetime(&start().naive_utc()).fix(), TimezoneSource::System));
+    }
+}
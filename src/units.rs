@@ -0,0 +1,84 @@
+// ============================================================================
+// src/units.rs - Unidades de medida e formatos regionais de data e hora
+// ============================================================================
+
+use chrono::{DateTime, FixedOffset};
+use clap::ValueEnum;
+
+const METERS_PER_MILE: f64 = 1609.344;
+const FEET_PER_METER: f64 = 3.280_84;
+
+/// Sistema de unidades das distâncias, ritmos, velocidades e altitudes do overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Units {
+    /// km, min/km, km/h e metros
+    #[default]
+    Metric,
+    /// milhas, min/mi, mph e pés
+    Imperial,
+}
+
+impl Units {
+    /// Distância com duas casas (ex: "5.02 km", "3.12 mi")
+    pub fn format_distance(self, meters: f64) -> String {
+        match self {
+            Units::Metric => format!("{:.2} km", meters / 1000.0),
+            Units::Imperial => format!("{:.2} mi", meters / METERS_PER_MILE),
+        }
+    }
+
+    /// Altitude sem casas decimais (ex: "230 m", "755 ft")
+    pub fn format_altitude(self, meters: f64) -> String {
+        match self {
+            Units::Metric => format!("{:.0} m", meters),
+            Units::Imperial => format!("{:.0} ft", meters * FEET_PER_METER),
+        }
+    }
+
+    /// Ritmo (min por km/milha) ou velocidade (km/h, mph) a partir de m/s
+    pub fn format_pace_or_speed(self, speed_mps: f64, as_pace: bool) -> String {
+        let (unit_meters, pace_unit, speed_unit) = match self {
+            Units::Metric => (1000.0, "/km", "km/h"),
+            Units::Imperial => (METERS_PER_MILE, "/mi", "mph"),
+        };
+        if as_pace {
+            let seconds = (unit_meters / speed_mps).round() as u32;
+            format!("{}:{:02} {}", seconds / 60, seconds % 60, pace_unit)
+        } else {
+            format!("{:.1} {}", speed_mps * 3600.0 / unit_meters, speed_unit)
+        }
+    }
+}
+
+/// Formato regional da data e da hora exibidas no overlay e no resumo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Locale {
+    /// 20/08/2024 e 06:00
+    #[default]
+    #[value(name = "pt-br")]
+    PtBr,
+    /// 08/20/2024 e 6:00 AM
+    #[value(name = "en-us")]
+    EnUs,
+    /// 20/08/2024 e 06:00
+    #[value(name = "en-gb")]
+    EnGb,
+}
+
+impl Locale {
+    /// Data da atividade (ex: "20/08/2024")
+    pub fn format_date(self, time: DateTime<FixedOffset>) -> String {
+        match self {
+            Locale::EnUs => time.format("%m/%d/%Y").to_string(),
+            Locale::PtBr | Locale::EnGb => time.format("%d/%m/%Y").to_string(),
+        }
+    }
+
+    /// Hora da atividade (ex: "06:00", "6:00 AM")
+    pub fn format_time(self, time: DateTime<FixedOffset>) -> String {
+        match self {
+            Locale::EnUs => time.format("%-I:%M %p").to_string(),
+            Locale::PtBr | Locale::EnGb => time.format("%H:%M").to_string(),
+        }
+    }
+}
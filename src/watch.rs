@@ -0,0 +1,216 @@
+// ============================================================================
+// src/watch.rs - Modo watch: processa automaticamente fotos e atividades novas
+// ============================================================================
+
+use notify::RecursiveMode;
+use notify_debouncer_mini::new_debouncer;
+use log::{error, info, warn};
+use std::collections::{BTreeSet, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::batch::{self, LoadedActivity, ACTIVITY_EXTENSIONS, PHOTO_EXTENSIONS};
+use crate::cli::WatchArgs;
+use tcx_image_overlay::{parse_activity_file, AppError, AppResult, SaveOutcome};
+
+/// Arquivo (dentro de ~/stats_overlay/) com as fotos já processadas
+const WATCH_STATE_FILE: &str = ".watch_state";
+
+/// Fotos já processadas, gravadas como "mtime<TAB>foto<TAB>saída" por linha
+/// (a saída é opcional nos arquivos de versões anteriores). Uma foto
+/// modificada depois do processamento (mtime diferente) é refeita.
+struct WatchState {
+    path: PathBuf,
+    processed: HashSet<(u64, PathBuf)>,
+    /// Imagens gravadas pela ferramenta (caminhos canônicos), ignoradas para
+    /// evitar laços quando a saída fica dentro do diretório observado
+    outputs: HashSet<PathBuf>,
+}
+
+impl WatchState {
+    fn load(path: PathBuf) -> AppResult<Self> {
+        let mut processed = HashSet::new();
+        let mut outputs = HashSet::new();
+        match fs::read_to_string(&path) {
+            Ok(contents) => {
+                for line in contents.lines() {
+                    let mut fields = line.splitn(3, '\t');
+                    let (Some(mtime), Some(photo)) = (fields.next(), fields.next()) else { continue };
+                    let Ok(mtime) = mtime.parse() else { continue };
+                    processed.insert((mtime, PathBuf::from(photo)));
+                    outputs.extend(fields.next().map(PathBuf::from));
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        Ok(Self { path, processed, outputs })
+    }
+
+    fn contains(&self, photo: &Path) -> bool {
+        modified_secs(photo).is_some_and(|mtime| self.processed.contains(&(mtime, photo.to_path_buf())))
+    }
+
+    /// Indica se o arquivo é uma imagem gravada pela própria ferramenta
+    fn is_output(&self, path: &Path) -> bool {
+        fs::canonicalize(path).is_ok_and(|path| self.outputs.contains(&path))
+    }
+
+    /// Registra a foto e a imagem gerada e acrescenta a linha ao arquivo de estado
+    fn insert(&mut self, photo: &Path, output: &Path) -> AppResult<()> {
+        let output = fs::canonicalize(output).unwrap_or_else(|_| output.to_path_buf());
+        self.outputs.insert(output.clone());
+        let Some(mtime) = modified_secs(photo) else {
+            return Ok(());
+        };
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}\t{}\t{}", mtime, photo.display(), output.display())?;
+        self.processed.insert((mtime, photo.to_path_buf()));
+        Ok(())
+    }
+}
+
+/// Estado da sessão: atividades conhecidas e fotos aguardando uma atividade
+struct WatchSession<'a> {
+    args: &'a WatchArgs,
+    state: WatchState,
+    activities: Vec<LoadedActivity>,
+    pending_photos: BTreeSet<PathBuf>,
+}
+
+/// Executa o subcomando `watch`
+pub fn run(args: &WatchArgs) -> AppResult<()> {
+    let state_path = match &args.state_file {
+        Some(path) => path.clone(),
+        None => dirs::home_dir()
+            .ok_or_else(|| AppError::InvalidFormat("Não foi possível determinar o diretório home do usuário".to_string()))?
+            .join("stats_overlay")
+            .join(WATCH_STATE_FILE),
+    };
+    info!("🗂️  Arquivo de estado: {:?}", state_path);
+
+    let mut session = WatchSession {
+        args,
+        state: WatchState::load(state_path)?,
+        activities: Vec::new(),
+        pending_photos: BTreeSet::new(),
+    };
+
+    // Atividades já existentes sempre são carregadas (fotos novas podem ser de treinos antigos);
+    // fotos existentes só com --initial-scan
+    let inputs: Vec<String> = args.directories.iter().map(|d| d.display().to_string()).collect();
+    for activity in batch::collect_files(&inputs, ACTIVITY_EXTENSIONS)? {
+        session.load_activity(&activity);
+    }
+    if args.initial_scan {
+        session.handle_paths(batch::collect_files(&inputs, PHOTO_EXTENSIONS)?.into_iter().collect());
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut debouncer = new_debouncer(Duration::from_millis(args.debounce_ms), tx)
+        .map_err(|e| AppError::InvalidFormat(format!("Falha ao iniciar o observador: {}", e)))?;
+    for directory in &args.directories {
+        debouncer
+            .watcher()
+            .watch(directory, RecursiveMode::NonRecursive)
+            .map_err(|e| AppError::InvalidFormat(format!("Falha ao observar {:?}: {}", directory, e)))?;
+        info!("👀 Observando {:?}", directory);
+    }
+    info!("⏳ Aguardando novos arquivos (Ctrl+C para sair)...");
+
+    for result in rx {
+        match result {
+            Ok(events) => session.handle_paths(events.into_iter().map(|event| event.path).collect()),
+            Err(e) => warn!("⚠️  Erro do observador: {}", e),
+        }
+    }
+    Ok(())
+}
+
+impl WatchSession<'_> {
+    /// Trata um lote de caminhos alterados (já agrupados pelo debounce)
+    fn handle_paths(&mut self, paths: BTreeSet<PathBuf>) {
+        let mut new_activity = false;
+        for path in paths.iter().filter(|p| p.is_file()) {
+            if batch::has_extension(path, ACTIVITY_EXTENSIONS) {
+                new_activity |= self.load_activity(path);
+            } else if batch::has_extension(path, PHOTO_EXTENSIONS)
+                && !self.state.is_output(path)
+                && !self.state.contains(path)
+            {
+                self.pending_photos.insert(path.clone());
+            }
+        }
+
+        // Uma atividade nova pode ser a que faltava para fotos sincronizadas antes
+        if new_activity || !self.pending_photos.is_empty() {
+            self.process_pending();
+        }
+    }
+
+    /// Carrega (ou recarrega) uma atividade; retorna se foi lida com sucesso
+    fn load_activity(&mut self, path: &Path) -> bool {
+        match parse_activity_file(&path.to_path_buf()) {
+            Ok(data) => {
+                info!("🏃 Atividade carregada: {:?}", path);
+                self.activities.retain(|activity| activity.path != path);
+                self.activities.push(LoadedActivity { path: path.to_path_buf(), data });
+                true
+            }
+            Err(e) => {
+                warn!("⚠️  Ignorando atividade {:?}: {}", path, e);
+                false
+            }
+        }
+    }
+
+    /// Processa as fotos pendentes que já têm atividade correspondente
+    fn process_pending(&mut self) {
+        let pending: Vec<PathBuf> = self.pending_photos.iter().cloned().collect();
+        for photo in pending {
+            let overlay = &self.args.overlay;
+            let Some(activity) = batch::match_activity(
+                &photo,
+                &self.activities,
+                self.args.match_mode,
+                overlay.timezone.as_ref(),
+            ) else {
+                info!("⏸️  {:?}: aguardando a atividade correspondente", photo);
+                continue;
+            };
+
+            // Falhas saem da fila; a foto é tentada de novo se for modificada
+            self.pending_photos.remove(&photo);
+            match batch::render_photo(&photo, None, activity, overlay, self.args.output_dir.as_deref()) {
+                Ok(outcome) => {
+                    let output = match outcome {
+                        SaveOutcome::Written(output) => {
+                            info!("✅ {:?} → {:?}", photo, output);
+                            output
+                        }
+                        SaveOutcome::Skipped(output) => {
+                            info!("⏭️  {:?}: {:?} já existe", photo, output);
+                            output
+                        }
+                    };
+                    if let Err(e) = self.state.insert(&photo, &output) {
+                        warn!("⚠️  Falha ao gravar o arquivo de estado: {}", e);
+                    }
+                }
+                Err(e) => error!("❌ {:?}: {}", photo, e),
+            }
+        }
+    }
+}
+
+/// Data de modificação do arquivo em segundos desde a época Unix
+fn modified_secs(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    modified.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}